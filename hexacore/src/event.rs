@@ -0,0 +1,73 @@
+//! Recording of game events per turn, e.g. for UI feedback.
+
+use std::mem;
+
+/// A log of the events of the current turn of a game, e.g. the effects
+/// of ending a turn ("Shipyard produced capacity"), to be surfaced by
+/// the UI or to support undo.
+///
+/// Events are recorded during a turn and drained at its end,
+/// which advances the log to the next turn.
+#[derive(Clone, Debug)]
+pub struct Log<E> {
+    /// The current turn, starting at 0.
+    turn: u64,
+    /// The events of the current turn, in the order recorded.
+    events: Vec<E>,
+}
+
+impl<E> Default for Log<E> {
+    fn default() -> Self {
+        Log::new()
+    }
+}
+
+impl<E> Log<E> {
+    /// Create a new, empty log, beginning at turn 0.
+    pub fn new() -> Log<E> {
+        Log { turn: 0, events: Vec::new() }
+    }
+
+    /// The current turn, i.e. the number of turns drained so far.
+    pub fn turn(&self) -> u64 {
+        self.turn
+    }
+
+    /// Record an event of the current turn.
+    pub fn record(&mut self, e: E) {
+        self.events.push(e)
+    }
+
+    /// The events recorded during the current turn so far.
+    pub fn events(&self) -> &[E] {
+        &self.events
+    }
+
+    /// Take the events of the current turn, in the order in which they
+    /// were recorded, and advance the log to the next turn.
+    pub fn drain_turn(&mut self) -> Vec<E> {
+        self.turn += 1;
+        mem::take(&mut self.events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_turn() {
+        let mut log = Log::new();
+        log.record("ship range restored");
+        log.record("shipyard produced capacity");
+        assert_eq!(log.events().len(), 2);
+        assert_eq!(log.drain_turn(), vec!["ship range restored", "shipyard produced capacity"]);
+        assert_eq!(log.turn(), 1);
+        assert!(log.events().is_empty());
+        log.record("shipyard produced capacity");
+        assert_eq!(log.drain_turn(), vec!["shipyard produced capacity"]);
+        assert_eq!(log.turn(), 2);
+        assert!(log.drain_turn().is_empty());
+        assert_eq!(log.turn(), 3);
+    }
+}
@@ -3,6 +3,23 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 //! Geometry of regular hexagons in a 2d cartesian coordinate system.
+//!
+//! The y-axis of the coordinate system points downwards, as is common
+//! for screen coordinates.
+//!
+//! # Corners and edges
+//!
+//! The corners of a [`Hexagon`] are ordered by increasing angle from the
+//! positive x-axis, i.e. clockwise on the screen, starting at the corner
+//! with the angle [`Schema::first_corner_angle`]:
+//!
+//!   * For a flat-top orientation, corner `0` is the corner to the right
+//!     of the center, at an angle of 0 degrees.
+//!   * For a pointy-top orientation, corner `0` is the upper right corner,
+//!     at an angle of -30 degrees, i.e. the flat-top corners rotated by
+//!     30 degrees counterclockwise.
+//!
+//! Edge `i` of a hexagon is the edge between corners `i` and `(i + 1) % 6`.
 
 use nalgebra::core::{ Matrix2, Vector2 };
 use nalgebra::geometry::Point2;
@@ -16,8 +33,8 @@ use std::ops::{ Neg, Add, Sub };
 pub const ANGLE_DEGREES: f32 = 60.0;
 
 /// The angle (in radians) of the equilateral triangles that
-/// a hexagon is composed of, i.e. 60 degrees in radians.
-pub const ANGLE_RADIANS: f32 = 1.0471975512;
+/// a hexagon is composed of, i.e. [`ANGLE_DEGREES`] in radians.
+pub const ANGLE_RADIANS: f32 = std::f32::consts::FRAC_PI_3;
 
 /// The orientation of a regular hexagon.
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -56,6 +73,7 @@ pub struct Schema {
                to_pixel: Matrix2<f32>,
                from_pixel: Matrix2<f32>,
                first_corner_angle: f32,
+               scale_y: f32,
 }
 
 impl Schema {
@@ -81,6 +99,7 @@ impl Schema {
                     to_pixel,
                     from_pixel,
                     first_corner_angle: 0.,
+                    scale_y: 1.,
                 }
             }
             Orientation::PointyTop => {
@@ -98,13 +117,83 @@ impl Schema {
                     center_row_offset: 1.5 * size,
                     to_pixel,
                     from_pixel,
-                    first_corner_angle: ANGLE_RADIANS / 2.,
+                    first_corner_angle: -ANGLE_RADIANS / 2.,
+                    scale_y: 1.,
                 }
             }
         }
     }
 }
 
+impl Schema {
+    /// Create a schema from this schema for which the centers of hexagons
+    /// with integer coordinates (as computed by `to_pixel`) lie on integer
+    /// pixel coordinates, e.g. for crisp rendering of tiles without
+    /// anti-aliasing.
+    ///
+    /// To that end, the distances between the centers of adjacent hexagons
+    /// are rounded to integers (even integers along the staggered axis),
+    /// distorting the spacing of hexagons by less than a pixel, but to at
+    /// least one pixel (two along the staggered axis), such that even
+    /// hexagons smaller than a pixel have distinct centers. Hexagons
+    /// themselves remain regular and thus adjacent hexagons may overlap
+    /// or leave gaps of less than a pixel. The conversions between pixel
+    /// and hexagon coordinates remain consistent with each other.
+    pub fn snap_to_pixels(&self) -> Schema {
+        let size = self.side_len.0;
+        let mut schema = self.clone();
+        match self.orientation {
+            Orientation::FlatTop => {
+                let col = (1.5 * size).round().max(1.);
+                let row = 2. * (f32::sqrt(3.) / 2. * size * self.scale_y).round().max(1.);
+                schema.to_pixel = Matrix2::new(
+                    col,      0.,
+                    row / 2., row);
+                schema.height = row;
+                schema.center_col_offset = col;
+                schema.center_row_offset = row;
+            }
+            Orientation::PointyTop => {
+                let col = 2. * (f32::sqrt(3.) / 2. * size).round().max(1.);
+                let row = (1.5 * size * self.scale_y).round().max(1.);
+                schema.to_pixel = Matrix2::new(
+                    col, col / 2.,
+                    0.,  row);
+                schema.width = col;
+                schema.center_col_offset = col;
+                schema.center_row_offset = row;
+            }
+        }
+        schema.from_pixel = schema.to_pixel.try_inverse().unwrap();
+        schema
+    }
+
+    /// Create a schema from this schema for hexagons that are stretched
+    /// along the vertical axis by the given factor, which must be greater
+    /// than zero, e.g. for a perspective view of the grid.
+    ///
+    /// The conversions between pixel and hexagon coordinates as well
+    /// as the bounds of hexagons remain exact for the stretched hexagons,
+    /// whereas the side length and corner angles of the schema refer to
+    /// the regular hexagons before stretching.
+    pub fn stretch_y(&self, factor: f32) -> Schema {
+        assert!(factor > 0., "factor <= 0");
+        let mut schema = self.clone();
+        schema.to_pixel = Matrix2::new(1., 0., 0., factor) * self.to_pixel;
+        schema.from_pixel = schema.to_pixel.try_inverse().unwrap();
+        schema.height *= factor;
+        schema.center_row_offset *= factor;
+        schema.scale_y *= factor;
+        schema
+    }
+
+    /// The factor by which hexagons produced from this schema are
+    /// stretched along the vertical axis (see [`Schema::stretch_y`]).
+    pub fn scale_y(&self) -> f32 {
+        self.scale_y
+    }
+}
+
 impl Schema {
     /// The side length of hexagons produced from this schema.
     pub fn side_len(&self) -> f32 {
@@ -133,17 +222,77 @@ impl Schema {
         self.center_row_offset
     }
 
+    /// The matrix converting the coordinates of hexagons on an overlaid
+    /// coordinate system (as for [`Schema::to_pixel`]) into the pixel
+    /// coordinates of their centers, e.g. for custom calculations.
+    pub fn to_pixel_matrix(&self) -> Matrix2<f32> {
+        self.to_pixel
+    }
+
+    /// The inverse of the [`Schema::to_pixel_matrix`], as used by
+    /// [`Schema::from_pixel`].
+    pub fn from_pixel_matrix(&self) -> Matrix2<f32> {
+        self.from_pixel
+    }
+
     /// The orientation of the hexagons produced from this schema.
     pub fn orientation(&self) -> Orientation {
         self.orientation
     }
 
+    /// The angle (in radians) of the first corner of hexagons produced
+    /// from this schema, relative to the positive x-axis.
+    pub fn first_corner_angle(&self) -> f32 {
+        self.first_corner_angle
+    }
+
+    /// The angle (in radians) of the `i`-th corner of hexagons produced
+    /// from this schema, relative to the positive x-axis and consistent
+    /// with the order of [`Hexagon::corners`].
+    pub fn corner_angle(&self, i: u8) -> f32 {
+        ANGLE_RADIANS * i as f32 + self.first_corner_angle
+    }
+
     /// Create a hexagon centered at the given point according
     /// to the orientation and dimensions of this schema.
     pub fn hexagon(&self, center: Point2<f32>) -> Hexagon {
         Hexagon {
             center,
-            corners: self.corners(center, self.first_corner_angle),
+            corners: self.corners(center),
+        }
+    }
+
+    /// Create a hexagon centered at the given point, like [`Schema::hexagon`],
+    /// but with corners on integer pixel coordinates, e.g. for pixel-art.
+    ///
+    /// The corners are placed around the rounded center, at integer offsets
+    /// chosen such that adjacent hexagons share their corners if their
+    /// centers lie on integer pixel coordinates, as is the case for schemas
+    /// created by [`Schema::snap_to_pixels`], avoiding seams between the
+    /// hexagons. The hexagons are thus only approximately regular and
+    /// degenerate for side lengths of only a few pixels.
+    pub fn hexagon_snapped(&self, center: Point2<f32>) -> Hexagon {
+        let snapped = self.snap_to_pixels();
+        let (col, row) = (snapped.center_col_offset, snapped.center_row_offset);
+        let offsets = match self.orientation {
+            Orientation::FlatTop => {
+                let x = self.side_len().round();
+                let y = col - x;
+                let h = row / 2.;
+                [(x, 0.), (y, h), (-y, h), (-x, 0.), (-y, -h), (y, -h)]
+            }
+            Orientation::PointyTop => {
+                let w = col / 2.;
+                let t = (self.side_len() * self.scale_y).round();
+                let s = row - t;
+                [(w, -s), (w, s), (0., t), (-w, s), (-w, -s), (0., -t)]
+            }
+        };
+        let c = Point2::new(center.x.round(), center.y.round());
+        let corner = |i: usize| Point2::new(c.x + offsets[i].0, c.y + offsets[i].1);
+        Hexagon {
+            center,
+            corners: [corner(0), corner(1), corner(2), corner(3), corner(4), corner(5)],
         }
     }
 
@@ -170,6 +319,21 @@ impl Schema {
         Point2::from(c)
     }
 
+    /// The vectors from the center of a hexagon to the centers of its
+    /// neighbours, indexed by the direction of the neighbour, i.e. in
+    /// the order of the `FlatTopDirection`s or `PointyTopDirection`s,
+    /// depending on the orientation.
+    pub fn neighbour_offsets(&self) -> [Vector2<f32>; 6] {
+        let offset = |x: f32, z: f32| self.to_pixel * Vector2::new(x, z);
+        [ offset( 0., -1.)
+        , offset( 1., -1.)
+        , offset( 1.,  0.)
+        , offset( 0.,  1.)
+        , offset(-1.,  1.)
+        , offset(-1.,  0.)
+        ]
+    }
+
     /// Convert pixel coordinates into hexagon coordinates, satisfying
     /// ```ignore
     /// s.from_pixel(s.to_pixel(p)) == p
@@ -180,20 +344,35 @@ impl Schema {
         P::from(Point2::from(c))
     }
 
-    fn corners(&self, center: Point2<f32>, off: f32) -> [Point2<f32>; 6] {
-        [ self.corner(center, 0, off)
-        , self.corner(center, 1, off)
-        , self.corner(center, 2, off)
-        , self.corner(center, 3, off)
-        , self.corner(center, 4, off)
-        , self.corner(center, 5, off)
+    /// Check whether a point lies within the hexagon of this schema centered
+    /// at the given point, by converting the point into the hexagon
+    /// coordinates relative to the center, like [`Schema::from_pixel`].
+    ///
+    /// This takes constant time and agrees exactly with picking hexagons
+    /// of a grid by pixel coordinates, but assumes the regular tiling of
+    /// the schema, e.g. it considers points within the small gaps or
+    /// overlaps of [`Schema::snap_to_pixels`] to be contained in exactly
+    /// one of the adjacent hexagons. Use [`polygon_contains`] for an exact
+    /// test against the corners of a hexagon.
+    pub fn contains(&self, center: Point2<f32>, p: Point2<f32>) -> bool {
+        use crate::grid::Cube;
+        self.from_pixel::<Cube>(Point2::from(p - center)) == Cube::origin()
+    }
+
+    fn corners(&self, center: Point2<f32>) -> [Point2<f32>; 6] {
+        [ self.corner(center, 0)
+        , self.corner(center, 1)
+        , self.corner(center, 2)
+        , self.corner(center, 3)
+        , self.corner(center, 4)
+        , self.corner(center, 5)
         ]
     }
 
-    fn corner(&self, center: Point2<f32>, i: u8, off: f32) -> Point2<f32> {
-        let angle_rad = ANGLE_RADIANS * i as f32 - off;
+    fn corner(&self, center: Point2<f32>, i: u8) -> Point2<f32> {
+        let angle_rad = self.corner_angle(i);
         let x = center.x + self.side_len() * angle_rad.cos();
-        let y = center.y + self.side_len() * angle_rad.sin();
+        let y = center.y + self.side_len() * angle_rad.sin() * self.scale_y;
         Point2::new(x, y)
     }
 
@@ -211,6 +390,9 @@ pub enum VAlign {
 }
 
 /// A regular hexagon.
+///
+/// See the [module documentation](index.html#corners-and-edges) for the
+/// order of the corners.
 #[derive(PartialEq, Clone, Debug)]
 pub struct Hexagon {
     pub(crate) center: Point2<f32>,
@@ -222,14 +404,101 @@ impl Hexagon {
         self.center
     }
 
+    /// Check whether the center and corners of two hexagons differ by
+    /// at most `eps` in each coordinate, which is more robust than exact
+    /// equality for computed geometry.
+    pub fn approx_eq(&self, other: &Hexagon, eps: f32) -> bool {
+        approx_eq_point(self.center, other.center, eps) &&
+        self.corners.iter().zip(other.corners.iter())
+            .all(|(a, b)| approx_eq_point(*a, *b, eps))
+    }
+
+    /// The corners of the hexagon, ordered clockwise (on the screen)
+    /// starting with the corner at the angle of
+    /// [`Schema::first_corner_angle`].
     pub fn corners(&self) -> &[Point2<f32>; 6] {
         &self.corners
     }
+
+    /// The corners of the hexagon in the given winding order (on the
+    /// screen), both starting with the first corner of [`Hexagon::corners`],
+    /// e.g. for renderers that rely on the winding of polygons.
+    pub fn corners_ordered(&self, rot: Rotation) -> [Point2<f32>; 6] {
+        match rot {
+            Rotation::CW  => self.corners,
+            Rotation::CCW => {
+                let mut cs = self.corners;
+                cs[1 ..].reverse();
+                cs
+            }
+        }
+    }
+
+    /// The edges of the hexagon, whereby edge `i` is the line
+    /// between corners `i` and `(i + 1) % 6`.
+    pub fn edges(&self) -> impl Iterator<Item=Line> + '_ {
+        (0 .. 6).map(move |i| Line::new(self.corners[i], self.corners[(i + 1) % 6]))
+    }
+
+    /// Intersect a line segment with the hexagon, returning the points where
+    /// the line segment enters and exits the hexagon, in the direction of
+    /// the line segment, or `None` if the line segment does not intersect
+    /// the hexagon. Endpoints of the line segment within the hexagon are
+    /// returned as-is, i.e. the result is the part of the line segment
+    /// within the hexagon.
+    pub fn intersect_line(&self, line: &Line) -> Option<(Point2<f32>, Point2<f32>)> {
+        let [p, q] = line.0;
+        let d = q - p;
+        let (mut t_min, mut t_max) = (0f32, 1f32);
+        for Line([a, b]) in self.edges() {
+            // The normal of the edge, pointing outwards.
+            let e = b - a;
+            let mut n = Vector2::new(e.y, -e.x);
+            if n.dot(&(self.center - a)) > 0. {
+                n = -n;
+            }
+            let num = n.dot(&(p - a));
+            let den = n.dot(&d);
+            if den == 0. {
+                if num > 0. {
+                    return None
+                }
+            } else {
+                let t = -num / den;
+                if den < 0. {
+                    t_min = t_min.max(t);
+                } else {
+                    t_max = t_max.min(t);
+                }
+            }
+            if t_min > t_max {
+                return None
+            }
+        }
+        Some((p + d * t_min, p + d * t_max))
+    }
 }
 
+fn approx_eq_point(a: Point2<f32>, b: Point2<f32>, eps: f32) -> bool {
+    (a.x - b.x).abs() <= eps && (a.y - b.y).abs() <= eps
+}
+
+/// A line segment between two points.
 pub struct Line([Point2<f32>; 2]);
 
 impl Line {
+    pub fn new(a: Point2<f32>, b: Point2<f32>) -> Line {
+        Line([a, b])
+    }
+
+    pub fn start(&self) -> Point2<f32> {
+        self.0[0]
+    }
+
+    pub fn end(&self) -> Point2<f32> {
+        self.0[1]
+    }
+
     pub fn bounds(&self) -> Bounds {
         let [a,b] = self.0;
         Bounds {
@@ -250,6 +519,14 @@ pub struct Bounds {
 }
 
 impl Bounds {
+    /// Check whether the position and dimensions of two bounds differ
+    /// by at most `eps`, see [`Hexagon::approx_eq`].
+    pub fn approx_eq(&self, other: &Bounds, eps: f32) -> bool {
+        approx_eq_point(self.position, other.position, eps) &&
+        (self.width - other.width).abs() <= eps &&
+        (self.height - other.height).abs() <= eps
+    }
+
     /// Check whether the two bounds intersect.
     pub fn intersects(&self, b: &Bounds) -> bool {
         self.position.x               < b.position.x + b.width  &&
@@ -337,6 +614,102 @@ pub fn lerp(ai: i32, bi: i32, fr: Frac1) -> f32 {
     a + (b - a) * t
 }
 
+/// Linear interpolation between two points, yielding exactly `a`
+/// for `t = 0` and exactly `b` for `t = 1`.
+pub fn interpolate(a: Point2<f32>, b: Point2<f32>, t: Frac1) -> Point2<f32> {
+    let t = f32::from(t);
+    Point2::new(a.x * (1. - t) + b.x * t, a.y * (1. - t) + b.y * t)
+}
+
+/// An iterator over the points obtained by dividing the line between
+/// two points into a number of steps of equal length, including both
+/// the start and end points, e.g. for animating a continuous movement.
+#[derive(Clone, Debug)]
+pub struct LerpPoints {
+    a: Point2<f32>,
+    b: Point2<f32>,
+    steps: usize,
+    next: usize,
+}
+
+impl LerpPoints {
+    /// Divide the line between the given points into the given number
+    /// of steps, i.e. yielding `steps + 1` points. Zero steps yield
+    /// only the end point.
+    pub fn new(a: Point2<f32>, b: Point2<f32>, steps: usize) -> LerpPoints {
+        LerpPoints { a, b, steps, next: if steps == 0 { 1 } else { 0 } }
+    }
+}
+
+impl Iterator for LerpPoints {
+    type Item = Point2<f32>;
+
+    fn next(&mut self) -> Option<Point2<f32>> {
+        let n = self.steps.max(1);
+        if self.next > n {
+            return None
+        }
+        let t = Frac1::new(self.next as f32, n as f32);
+        self.next += 1;
+        Some(interpolate(self.a, self.b, t))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.steps.max(1) + 1).saturating_sub(self.next);
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for LerpPoints {}
+
+/// Check whether the given points are the corners of a convex,
+/// non-degenerate polygon, in either clockwise or counterclockwise
+/// order, i.e. whether there are at least 3 corners, consecutive
+/// edges always turn in the same direction and the edges wind around
+/// the polygon exactly once, which rules out self-intersecting
+/// polygons such as pentagrams.
+pub fn is_convex_polygon(ps: &[Point2<f32>]) -> bool {
+    let n = ps.len();
+    if n < 3 {
+        return false
+    }
+    let turns = (0 .. n).map(|i| {
+        let e1 = ps[(i + 1) % n] - ps[i];
+        let e2 = ps[(i + 2) % n] - ps[(i + 1) % n];
+        (e1.x * e2.y - e1.y * e2.x, e1.dot(&e2))
+    });
+    let mut sign = 0.;
+    let mut winding = 0.;
+    for (t, d) in turns {
+        if !t.is_finite() || t.abs() <= f32::EPSILON {
+            return false
+        }
+        if sign * t < 0. {
+            return false
+        }
+        sign = t.signum();
+        winding += f32::atan2(t, d);
+    }
+    (winding.abs() - 2. * std::f32::consts::PI).abs() < 1e-3
+}
+
+/// Check whether a point lies within the polygon with the given corners,
+/// according to the even-odd rule.
+pub fn polygon_contains(ps: &[Point2<f32>], p: Point2<f32>) -> bool {
+    let n = ps.len();
+    let mut inside = false;
+    for i in 0 .. n {
+        let (a, b) = (ps[i], ps[(i + 1) % n]);
+        if (a.y > p.y) != (b.y > p.y) {
+            let x = a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if p.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
 /// The additive group of integers modulo 6, i.e. Z/6Z,
 /// which is isomorphic to the group of rotational symmetries
 /// of a regular hexagon.
@@ -470,6 +843,23 @@ mod tests {
         quickcheck(prop as fn(_,_,_,_) -> _);
     }
 
+    #[test]
+    fn prop_snap_to_pixels_small() {
+        fn prop(x: i8, y: i8, s: u8, o: Orientation) -> bool {
+            let side = SideLength((s as f32 + 1.) / 257.);
+            let schema = Schema::new(side, o);
+            let s = schema.snap_to_pixels();
+            let p = s.to_pixel(Point2::new(x as f32, y as f32));
+            let c = s.from_pixel::<Point2<f32>>(p);
+            let hex = schema.hexagon_snapped(p);
+            s.center_col_offset >= 1. && s.center_row_offset >= 1. &&
+            p.x.fract() == 0. && p.y.fract() == 0. &&
+            (c.x.round(), c.y.round()) == (x as f32, y as f32) &&
+            hex.corners().iter().all(|c| c.x.is_finite() && c.y.is_finite())
+        }
+        quickcheck(prop as fn(_,_,_,_) -> _);
+    }
+
     #[test]
     fn prop_to_pixel_distance() {
         // The distances of the x and y coordinates of any
@@ -495,6 +885,98 @@ mod tests {
         quickcheck(prop as fn(_,_,_) -> _);
     }
 
+    #[test]
+    fn prop_corner_angle() {
+        fn prop(x: i16, y: i16, s: SideLength, o: Orientation) -> bool {
+            let s = Schema::new(s, o);
+            let hex = s.hexagon(Point2::new(x as f32, y as f32));
+            hex.corners().iter().enumerate().all(|(i, c)| {
+                let angle = s.corner_angle(i as u8);
+                let x = hex.center.x + s.side_len() * angle.cos();
+                let y = hex.center.y + s.side_len() * angle.sin();
+                (c.x - x).abs() < 0.001 && (c.y - y).abs() < 0.001
+            }) && s.corner_angle(0) == s.first_corner_angle()
+        }
+        quickcheck(prop as fn(_,_,_,_) -> _);
+    }
+
+    fn assert_near(p: Point2<f32>, x: f32, y: f32) {
+        assert!((p.x - x).abs() < 0.001 && (p.y - y).abs() < 0.001,
+            "{:?} != ({}, {})", p, x, y);
+    }
+
+    #[test]
+    fn test_corner_order_flat_top() {
+        let s = Schema::new(SideLength(2.), Orientation::FlatTop);
+        let h = s.hexagon(Point2::origin());
+        let dy = f32::sqrt(3.);
+        assert_near(h.corners[0],  2.,  0.); // right
+        assert_near(h.corners[1],  1.,  dy); // lower right
+        assert_near(h.corners[2], -1.,  dy); // lower left
+        assert_near(h.corners[3], -2.,  0.); // left
+        assert_near(h.corners[4], -1., -dy); // upper left
+        assert_near(h.corners[5],  1., -dy); // upper right
+    }
+
+    #[test]
+    fn test_corner_order_pointy_top() {
+        let s = Schema::new(SideLength(2.), Orientation::PointyTop);
+        let h = s.hexagon(Point2::origin());
+        let dx = f32::sqrt(3.);
+        assert_near(h.corners[0],  dx, -1.); // upper right
+        assert_near(h.corners[1],  dx,  1.); // lower right
+        assert_near(h.corners[2],  0.,  2.); // bottom
+        assert_near(h.corners[3], -dx,  1.); // lower left
+        assert_near(h.corners[4], -dx, -1.); // upper left
+        assert_near(h.corners[5],  0., -2.); // top
+    }
+
+    #[test]
+    fn prop_snap_to_pixels() {
+        fn prop(x: i8, y: i8, s: SideLength, o: Orientation) -> bool {
+            let s = Schema::new(s, o).snap_to_pixels();
+            let p = s.to_pixel(Point2::new(x as f32, y as f32));
+            let neighbours = [(1, 0), (0, 1), (-1, 1), (-1, 0), (0, -1), (1, -1)];
+            p.x.fract() == 0. && p.y.fract() == 0. &&
+            neighbours.iter().all(|(dx, dy)| {
+                let n = s.to_pixel(Point2::new((x + dx) as f32, (y + dy) as f32));
+                let (dx, dy) = ((n.x - p.x).abs(), (n.y - p.y).abs());
+                match o {
+                    Orientation::FlatTop =>
+                        dx == 0. && dy == s.center_row_offset ||
+                        dx == s.center_col_offset && dy == s.center_row_offset / 2.,
+                    Orientation::PointyTop =>
+                        dy == 0. && dx == s.center_col_offset ||
+                        dy == s.center_row_offset && dx == s.center_col_offset / 2.,
+                }
+            }) &&
+            {
+                let c = s.from_pixel::<Point2<f32>>(p);
+                (c.x.round(), c.y.round()) == (x as f32, y as f32)
+            }
+        }
+        quickcheck(prop as fn(_,_,_,_) -> _);
+    }
+
+    #[test]
+    fn prop_stretch_y() {
+        fn round(p: Point2<f32>) -> Point2<i16> {
+            Point2::new(p.x.round() as i16, p.y.round() as i16)
+        }
+        fn prop(x: i16, y: i16, s: SideLength, o: Orientation, f: u8) -> bool {
+            let factor = 0.25 + (f % 8) as f32 * 0.25;
+            let s = Schema::new(s, o).stretch_y(factor);
+            let p = Point2::new(x as f32, y as f32);
+            let hex = s.hexagon(s.to_pixel(p));
+            let b = s.bounds(&hex);
+            round(s.from_pixel(s.to_pixel(p))) == round(p) &&
+            hex.corners().iter().all(|c| {
+                b.position.y - 0.01 <= c.y && c.y <= b.position.y + b.height + 0.01
+            })
+        }
+        quickcheck(prop as fn(_,_,_,_,_) -> _);
+    }
+
     #[test]
     fn prop_bounds() {
         fn prop(b: Bounds) -> bool {
@@ -504,5 +986,173 @@ mod tests {
         }
         quickcheck(prop as fn(_) -> _);
     }
+
+    #[test]
+    fn prop_hexagon_convex() {
+        fn prop(s: SideLength, o: Orientation, x: f32, y: f32) -> bool {
+            let schema = Schema::new(s, o);
+            is_convex_polygon(schema.hexagon(Point2::new(x, y)).corners())
+        }
+        quickcheck(prop as fn(_,_,_,_) -> _);
+    }
+
+    #[test]
+    fn test_degenerate_polygon() {
+        let p = Point2::new(1., 1.);
+        assert!(!is_convex_polygon(&[]));
+        assert!(!is_convex_polygon(&[p, Point2::new(2., 2.)]));
+        assert!(!is_convex_polygon(&[p; 6]));
+        let line = (0 .. 6).map(|i| Point2::new(i as f32, 0.)).collect::<Vec<_>>();
+        assert!(!is_convex_polygon(&line));
+        let bowtie = [
+            Point2::new(0., 0.), Point2::new(1., 1.),
+            Point2::new(1., 0.), Point2::new(0., 1.)
+        ];
+        assert!(!is_convex_polygon(&bowtie));
+        let pentagon = (0 .. 5)
+            .map(|i| i as f32 * 2. * std::f32::consts::PI / 5.)
+            .map(|a| Point2::new(a.cos(), a.sin()))
+            .collect::<Vec<_>>();
+        assert!(is_convex_polygon(&pentagon));
+        let pentagram = [0, 2, 4, 1, 3].iter().map(|i| pentagon[*i]).collect::<Vec<_>>();
+        assert!(!is_convex_polygon(&pentagram));
+    }
+
+    #[test]
+    fn prop_hexagon_intersect_line() {
+        fn prop(s: SideLength, o: Orientation, angle: u16) -> bool {
+            let schema = Schema::new(s, o);
+            let center = Point2::new(100., 100.);
+            let hex = schema.hexagon(center);
+            let angle = f32::from(angle % 360).to_radians();
+            let v = Vector2::new(angle.cos(), angle.sin()) * schema.width();
+            // A line through the center.
+            let (a, b) = hex.intersect_line(&Line::new(center - v, center + v)).unwrap();
+            let through = nalgebra::distance(&nalgebra::center(&a, &b), &center) < 0.01
+                && (a - center).dot(&v) < 0.
+                && nalgebra::distance(&center, &a) >= schema.width().min(schema.height()) / 2. - 0.01
+                && nalgebra::distance(&center, &a) <= schema.side_len() + 0.01;
+            // A line outside of the hexagon.
+            let w = Vector2::new(-v.y, v.x);
+            let outside = hex.intersect_line(&Line::new(center + w - v, center + w + v)).is_none();
+            // A line within the hexagon.
+            let u = v * 0.1;
+            let (c, d) = hex.intersect_line(&Line::new(center - u, center + u)).unwrap();
+            let within = nalgebra::distance(&c, &(center - u)) < 0.01
+                && nalgebra::distance(&d, &(center + u)) < 0.01;
+            through && outside && within
+        }
+        quickcheck(prop as fn(_,_,_) -> _);
+    }
+
+    #[test]
+    fn prop_interpolate_endpoints() {
+        fn prop(ax: f32, ay: f32, bx: f32, by: f32, t: Frac1, steps: u8) -> bool {
+            let (a, b) = (Point2::new(ax, ay), Point2::new(bx, by));
+            let mid = interpolate(a, b, t);
+            let points = LerpPoints::new(a, b, steps as usize).collect::<Vec<_>>();
+            interpolate(a, b, Frac1::new(0., 1.)) == a &&
+            interpolate(a, b, Frac1::new(1., 1.)) == b &&
+            f32::min(a.x, b.x) <= mid.x && mid.x <= f32::max(a.x, b.x) &&
+            points.len() == LerpPoints::new(a, b, steps as usize).len() &&
+            points.len() == (steps as usize).max(1) + 1 - if steps == 0 { 1 } else { 0 } &&
+            points.last() == Some(&b) &&
+            (steps == 0 || points.first() == Some(&a))
+        }
+        quickcheck(prop as fn(_,_,_,_,_,_) -> _);
+    }
+
+    #[test]
+    fn prop_approx_eq() {
+        fn prop(s: SideLength, o: Orientation, x: i16, y: i16) -> bool {
+            let schema = Schema::new(s, o);
+            let center = Point2::new(x as f32, y as f32);
+            let moved = Point2::new(center.x + 0.001, center.y - 0.001);
+            let far = Point2::new(center.x + 1., center.y);
+            let (h1, h2, h3) = (schema.hexagon(center), schema.hexagon(moved), schema.hexagon(far));
+            let (b1, b2, b3) = (schema.bounds(&h1), schema.bounds(&h2), schema.bounds(&h3));
+            h1.approx_eq(&h1, 0.) && h1.approx_eq(&h2, 0.01) && !h1.approx_eq(&h3, 0.01) &&
+            b1.approx_eq(&b1, 0.) && b1.approx_eq(&b2, 0.01) && !b1.approx_eq(&b3, 0.01)
+        }
+        quickcheck(prop as fn(_,_,_,_) -> _);
+    }
+
+    #[test]
+    fn prop_hexagon_snapped() {
+        fn prop(s: SideLength, o: Orientation, x: i8, z: i8) -> bool {
+            let schema = Schema::new(SideLength(s.0 + 3.), o).snap_to_pixels();
+            let center = schema.to_pixel(Point2::new(x as f32, z as f32));
+            let hex = schema.hexagon_snapped(center);
+            let is_int = |p: &Point2<f32>| p.x.fract() == 0. && p.y.fract() == 0.;
+            // Adjacent hexagons share exactly two corners.
+            let shared = schema.neighbour_offsets().iter().all(|v| {
+                let other = schema.hexagon_snapped(center + v);
+                hex.corners().iter().filter(|p| other.corners().contains(p)).count() == 2
+            });
+            hex.center() == center &&
+            hex.corners().iter().all(is_int) &&
+            is_convex_polygon(hex.corners()) &&
+            hex.approx_eq(&schema.hexagon(center), 2.) &&
+            shared
+        }
+        quickcheck(prop as fn(_,_,_,_) -> _);
+    }
+
+    #[test]
+    fn test_angle_radians() {
+        assert!((ANGLE_RADIANS - ANGLE_DEGREES.to_radians()).abs() < 1e-6);
+        assert!((ANGLE_RADIANS - 60f32.to_radians()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn prop_schema_matrices() {
+        fn prop(s: SideLength, o: Orientation, x: i8, y: i8) -> bool {
+            let schema = Schema::new(s, o).stretch_y(0.8);
+            let p = Point2::new(x as f32, y as f32);
+            let q = schema.to_pixel(p);
+            let identity = schema.from_pixel_matrix() * schema.to_pixel_matrix();
+            Point2::from(schema.to_pixel_matrix() * p.coords) == q &&
+            (identity - Matrix2::identity()).abs().max() < 1e-4
+        }
+        quickcheck(prop as fn(_,_,_,_) -> _);
+    }
+
+    #[test]
+    fn prop_schema_contains() {
+        fn prop(s: SideLength, o: Orientation, cx: i16, cy: i16, dx: i8, dy: i8) -> bool {
+            let schema = Schema::new(s, o);
+            let center = Point2::new(cx as f32, cy as f32);
+            let p = center + Vector2::new(dx as f32, dy as f32) * s.0 / 64.;
+            let scaled = |f: f32| Schema::new(SideLength(s.0 * f), o).hexagon(center);
+            if polygon_contains(scaled(0.95).corners(), p) {
+                schema.contains(center, p)
+            } else if !polygon_contains(scaled(1.05).corners(), p) {
+                !schema.contains(center, p)
+            } else {
+                // Near the edges, the tests may disagree.
+                true
+            }
+        }
+        quickcheck(prop as fn(_,_,_,_,_,_) -> _);
+    }
+
+    #[test]
+    fn prop_corners_ordered() {
+        fn prop(x: i16, y: i16, s: SideLength, o: Orientation) -> bool {
+            let hex = Schema::new(s, o).hexagon(Point2::new(x as f32, y as f32));
+            let cw = hex.corners_ordered(Rotation::CW);
+            let ccw = hex.corners_ordered(Rotation::CCW);
+            // Twice the signed area, which is positive for clockwise
+            // winding on the screen, i.e. with the y-axis pointing down.
+            let area = |ps: &[Point2<f32>; 6]| (0 .. 6).map(|i| {
+                let (a, b) = (ps[i], ps[(i + 1) % 6]);
+                (a.x - x as f32) * (b.y - y as f32) - (b.x - x as f32) * (a.y - y as f32)
+            }).sum::<f32>();
+            cw == *hex.corners() &&
+            (0 .. 6).all(|i| ccw[i] == cw[(6 - i) % 6]) &&
+            area(&cw) > 0. && area(&ccw) < 0.
+        }
+        quickcheck(prop as fn(_,_,_,_) -> _);
+    }
 }
 
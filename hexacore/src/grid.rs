@@ -1,6 +1,9 @@
 //! Hexagonal grids with overlaid coordinate systems.
 pub mod shape;
 pub mod coords;
+pub mod fog;
+#[cfg(feature = "gen")]
+pub mod gen;
 pub use coords::*;
 
 use crate::geo::*;
@@ -8,15 +11,38 @@ use crate::grid::shape::Shape;
 
 use nalgebra::core::Vector2;
 use nalgebra::geometry::Point2;
-use std::collections::HashMap;
+use smallvec::SmallVec;
+use std::cmp::Ordering;
+use std::collections::{ HashMap, HashSet, hash_map };
+use std::iter;
+use std::sync::OnceLock;
 
 /// A grid is a contiguous arrangement of hexagonal tiles with
 /// an overlaid coordinate system.
+///
+/// Every tile may optionally carry a payload of type `T`, e.g.
+/// terrain or other game-specific data.
 #[derive(Clone, Debug)]
-pub struct Grid<C: Coords> {
+pub struct Grid<C: Coords, T = ()> {
     schema: Schema,
     store: HashMap<C, Hexagon>, // TODO: Configurable spatial hashing.
+    payload: HashMap<C, T>,
     dimensions: Dimensions,
+    /// The neighbours of every tile within the grid, computed lazily,
+    /// see [`Grid::cached_neighbours`].
+    adjacency: OnceLock<HashMap<C, SmallVec<[C; 6]>>>,
+}
+
+/// Detailed information about a tile picked by its pixel coordinates,
+/// see [`Grid::pick_detailed`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PickInfo<C> {
+    pub coords: C,
+    pub hexagon: Hexagon,
+    /// The index of the corner of the hexagon nearest to the picked point.
+    pub corner: usize,
+    /// The index of the edge of the hexagon nearest to the picked point.
+    pub edge: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -26,13 +52,40 @@ pub struct Dimensions {
     pub pixel_offset: Vector2<f32>
 }
 
-impl<C: Coords> Grid<C> {
+impl<C: Coords, T> Grid<C, T> {
     /// Constructs a new grid whose tiles conform to the given schema.
-    pub fn new<I>(schema: Schema, shape: Shape<I>) -> Grid<C>
+    /// Initially, the tiles carry no payload.
+    ///
+    /// The `total` of the shape is used to preallocate the grid and must
+    /// match the number of coordinates of the shape, which is validated
+    /// in debug builds. Use [`Grid::new_exact`] for shapes whose `total`
+    /// may be wrong.
+    pub fn new<I>(schema: Schema, shape: Shape<I>) -> Grid<C, T>
     where I: IntoIterator<Item=Cube> {
         let num_hexagons = shape.total;
         let (ps, cs): (Vec<Point2<f32>>, Vec<C>) =
             shape.into_iter().map(|c| (c.to_pixel(&schema), C::from(c))).unzip();
+        debug_assert_eq!(ps.len(), num_hexagons,
+            "The total of the shape does not match its number of coordinates.");
+        Self::from_pixels(schema, ps, cs, num_hexagons)
+    }
+
+    /// Like [`Grid::new`], but ignoring the `total` of the shape,
+    /// preallocating the grid for the actual number of coordinates
+    /// of the shape instead.
+    pub fn new_exact<I>(schema: Schema, shape: Shape<I>) -> Grid<C, T>
+    where I: IntoIterator<Item=Cube> {
+        let (ps, cs): (Vec<Point2<f32>>, Vec<C>) =
+            shape.into_iter().map(|c| (c.to_pixel(&schema), C::from(c))).unzip();
+        let num_hexagons = ps.len();
+        Self::from_pixels(schema, ps, cs, num_hexagons)
+    }
+
+    /// Constructs a new grid from the coordinates and the (unadjusted)
+    /// pixel coordinates of the centers of its tiles.
+    fn from_pixels(schema: Schema, ps: Vec<Point2<f32>>, cs: Vec<C>, num_hexagons: usize)
+        -> Grid<C, T>
+    {
         let dimensions = Self::measure(&schema, &ps);
         let offset = dimensions.pixel_offset;
         let store = {
@@ -44,13 +97,18 @@ impl<C: Coords> Grid<C> {
         Grid {
             schema,
             store,
+            payload: HashMap::new(),
             dimensions,
+            adjacency: OnceLock::new(),
         }
     }
 
     /// Measures the dimensions of a grid, given the schema for the tiles and
-    /// the coordinates of the tile centers.
+    /// the coordinates of the tile centers. An empty grid has no extent.
     fn measure(schema: &Schema, centers: &Vec<Point2<f32>>) -> Dimensions {
+        if centers.is_empty() {
+            return Dimensions { width: 0., height: 0., pixel_offset: Vector2::zeros() }
+        }
         let min_max = (Point2::origin(), Point2::origin());
         let (min, max) = centers.iter().fold(min_max, |(min, max), c| {
              let new_min_x = f32::min(min.x, c.x);
@@ -70,16 +128,155 @@ impl<C: Coords> Grid<C> {
         }
     }
 
+    /// Create a new grid with the same tiles, payloads and geometry as
+    /// this grid, overlaid with a different coordinate system.
+    pub fn reproject<D: Coords>(&self) -> Grid<D, T>
+    where T: Clone {
+        let store = self.store.iter()
+            .map(|(c, h)| (D::from((*c).into()), h.clone()))
+            .collect();
+        let payload = self.payload.iter()
+            .map(|(c, t)| (D::from((*c).into()), t.clone()))
+            .collect();
+        Grid {
+            schema: self.schema.clone(),
+            store,
+            payload,
+            dimensions: self.dimensions.clone(),
+            adjacency: OnceLock::new(),
+        }
+    }
+
+    /// Create a new grid with the same tiles and geometry as this grid,
+    /// whose payloads are obtained by applying the given function to
+    /// the payloads of this grid.
+    pub fn map_payload<U, F>(&self, f: F) -> Grid<C, U>
+    where F: Fn(&C, &T) -> U {
+        Grid {
+            schema: self.schema.clone(),
+            store: self.store.clone(),
+            payload: self.payload.iter().map(|(c, t)| (*c, f(c, t))).collect(),
+            dimensions: self.dimensions.clone(),
+            adjacency: OnceLock::new(),
+        }
+    }
+
     pub fn schema(&self) -> &Schema {
         &self.schema
     }
 
+    /// Change the schema of the grid, rebuilding the geometry (i.e. the
+    /// hexagons and dimensions) of all tiles, while retaining the
+    /// coordinates and payloads. The schema is not exposed mutably,
+    /// since the geometry must be kept consistent with it.
+    pub fn set_schema(&mut self, schema: Schema) {
+        self.schema = schema;
+        let cs = self.store.keys().copied().collect();
+        self.layout(cs);
+    }
+
+    /// Insert the tiles of a whole shape into the grid, e.g. a newly
+    /// revealed region, retaining the existing tiles and payloads. The
+    /// geometry of the grid is recomputed once for all inserted tiles,
+    /// which may move the existing hexagons if the grid grows to the
+    /// left or top.
+    pub fn insert_shape<I>(&mut self, shape: I)
+    where I: IntoIterator<Item=Cube> {
+        let mut cs = self.store.keys().copied().collect::<Vec<_>>();
+        cs.extend(shape.into_iter().map(C::from).filter(|c| !self.store.contains_key(c)));
+        self.layout(cs);
+        self.adjacency = OnceLock::new();
+    }
+
+    /// (Re)compute the geometry of the grid for the given coordinates,
+    /// which become the tiles of the grid, as per the schema of the grid.
+    fn layout(&mut self, cs: Vec<C>) {
+        let ps = cs.iter().map(|c| c.to_cube().to_pixel(&self.schema)).collect();
+        let dimensions = Self::measure(&self.schema, &ps);
+        let offset = dimensions.pixel_offset;
+        let schema = &self.schema;
+        self.store = cs.into_iter().zip(ps).map(|(c, p)| (c, schema.hexagon(p + offset))).collect();
+        self.dimensions = dimensions;
+    }
+
     pub fn from_pixel(&self, p: Point2<f32>) -> Option<(C, &Hexagon)> {
         let offset = self.dimensions.pixel_offset;
         let c = C::from(Cube::from_pixel(p - offset, &self.schema));
         self.store.get(&c).map(|h| (c, h))
     }
 
+    /// Get a tile by its pixel coordinates, like [`Grid::from_pixel`],
+    /// together with the corner and edge of the hexagon nearest to the
+    /// given pixel coordinates, e.g. for editing features of a tile.
+    pub fn pick_detailed(&self, p: Point2<f32>) -> Option<PickInfo<C>> {
+        let (coords, hexagon) = self.from_pixel(p)?;
+        let cs = hexagon.corners();
+        let nearest = |d: &dyn Fn(usize) -> f32| (0 .. 6)
+            .min_by(|i, j| d(*i).partial_cmp(&d(*j)).unwrap_or(Ordering::Equal))
+            .unwrap_or(0);
+        let corner = nearest(&|i| nalgebra::distance_squared(&p, &cs[i]));
+        let edge = nearest(&|i| segment_distance(p, cs[i], cs[(i + 1) % 6]));
+        Some(PickInfo { coords, hexagon: hexagon.clone(), corner, edge })
+    }
+
+    /// Get a tile by its pixel coordinates, like [`Grid::from_pixel`], but
+    /// additionally resolving pixel coordinates outside of the grid to the
+    /// closest tile if they are within the given distance of its edges,
+    /// e.g. to tolerate imprecise clicks at the edges of the grid.
+    pub fn from_pixel_within(&self, p: Point2<f32>, tolerance: f32) -> Option<(C, &Hexagon)> {
+        self.from_pixel(p).or_else(|| {
+            self.closest(p).filter(|(_, h)| {
+                let cs = h.corners();
+                let d = (0 .. 6)
+                    .map(|i| segment_distance(p, cs[i], cs[(i + 1) % 6]))
+                    .fold(f32::INFINITY, f32::min);
+                d <= tolerance
+            })
+        })
+    }
+
+    /// Get the tile closest to the given pixel coordinates, which need not
+    /// lie within the grid. Only yields `None` if the grid is empty.
+    ///
+    /// The pixel coordinates are clamped to the bounding box of the grid,
+    /// such that only the tile at the clamped coordinates and its neighbours
+    /// are considered. Only if none of them is part of the grid, e.g. in a
+    /// corner of the bounding box of a hexagonal grid, are all tiles of the
+    /// grid considered. Among equally close tiles, the tile with the lowest
+    /// `x` and then `y` cube coordinates is chosen.
+    pub fn closest(&self, p: Point2<f32>) -> Option<(C, &Hexagon)> {
+        self.from_pixel(p).or_else(|| {
+            let dim = &self.dimensions;
+            let clamped = Point2::new(
+                f32::min(dim.width,  f32::max(0., p.x)),
+                f32::min(dim.height, f32::max(0., p.y)));
+            let c = Cube::from_pixel(clamped - dim.pixel_offset, &self.schema);
+            let around = iter::once(c).chain(c.neighbours())
+                .map(C::from)
+                .filter_map(|c| self.store.get_key_value(&c));
+            nearest(p, around).or_else(|| nearest(p, self.store.iter()))
+        })
+    }
+
+    /// Get the tile whose center is nearest to the given pixel coordinates,
+    /// if it is within the given radius, e.g. to tolerate imprecise taps on
+    /// touch screens. Unlike for [`Grid::from_pixel`], the pixel coordinates
+    /// need not lie within the hexagon of the tile. This takes time linear
+    /// in the size of the grid. Ties are broken as for [`Grid::closest`].
+    pub fn pick_radius(&self, p: Point2<f32>, r: f32) -> Option<(C, &Hexagon)> {
+        let within = self.store.iter()
+            .filter(|(_, h)| nalgebra::distance(&h.center, &p) <= r);
+        nearest(p, within)
+    }
+
+    /// Get the coordinates of the tile nearest to the geometric center of
+    /// the grid, e.g. as a default start position. Only yields `None` if
+    /// the grid is empty.
+    pub fn center_coord(&self) -> Option<C> {
+        let center = Point2::new(self.dimensions.width / 2., self.dimensions.height / 2.);
+        self.closest(center).map(|(c,_)| c)
+    }
+
     pub fn to_pixel(&self, c: C) -> Point2<f32> {
         let offset = self.dimensions.pixel_offset;
         c.into().to_pixel(&self.schema) + offset
@@ -89,10 +286,275 @@ impl<C: Coords> Grid<C> {
         self.store.get(&c)
     }
 
+    /// Get the payload of the tile with the given coordinates, if any.
+    pub fn payload(&self, c: C) -> Option<&T> {
+        self.payload.get(&c)
+    }
+
+    /// Get a mutable reference to the payload of the tile with the
+    /// given coordinates, if any.
+    pub fn payload_mut(&mut self, c: C) -> Option<&mut T> {
+        self.payload.get_mut(&c)
+    }
+
+    /// Set the payload of the tile with the given coordinates, returning
+    /// the previous payload, if any. If the grid has no tile with the
+    /// given coordinates, the payload is discarded.
+    pub fn set_payload(&mut self, c: C, t: T) -> Option<T> {
+        if self.store.contains_key(&c) {
+            self.payload.insert(c, t)
+        } else {
+            None
+        }
+    }
+
+    /// Remove the payload of the tile with the given coordinates,
+    /// returning it, if any.
+    pub fn remove_payload(&mut self, c: C) -> Option<T> {
+        self.payload.remove(&c)
+    }
+
+    /// Apply changes to the payloads of the grid, as computed by
+    /// [`Grid::diff`], i.e. set the given payloads and remove the
+    /// payloads given as `None`, e.g. to load an incremental autosave
+    /// or to undo changes. Payloads of coordinates that are not part of
+    /// the grid are ignored, as for [`Grid::set_payload`].
+    pub fn apply_patch(&mut self, patch: Vec<(C, Option<T>)>) {
+        for (c, t) in patch {
+            match t {
+                Some(t) => { self.set_payload(c, t); }
+                None => { self.remove_payload(c); }
+            }
+        }
+    }
+
+    /// Iterate over the tiles that carry a payload.
+    pub fn iter_payload(&self) -> impl Iterator<Item=(&C, &T)> + '_ {
+        self.payload.iter()
+    }
+
+    /// Check whether the grid has a tile with the given coordinates.
+    pub fn contains(&self, c: C) -> bool {
+        self.store.contains_key(&c)
+    }
+
+    /// The number of tiles in the grid.
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    /// Check whether the grid has no tiles.
+    pub fn is_empty(&self) -> bool {
+        self.store.is_empty()
+    }
+
+    /// The neighbours of the given coordinates that are part of the grid,
+    /// as a bitmask, whereby bit `i` is set if the neighbour in the
+    /// direction with index `i` (see [`Direction::index`]) exists, which
+    /// is e.g. the basis for autotiling.
+    pub fn neighbour_mask(&self, c: C) -> u8 {
+        self.neighbour_mask_where(c, |_| true)
+    }
+
+    /// Like [`Grid::neighbour_mask`], but only considering neighbours
+    /// whose payload satisfies the given predicate, whereby `None`
+    /// indicates a neighbour without payload.
+    pub fn neighbour_mask_where<F>(&self, c: C, pred: F) -> u8
+    where F: Fn(Option<&T>) -> bool {
+        coords::neighbours(c).enumerate()
+            .filter(|(_, n)| self.contains(*n) && pred(self.payload(*n)))
+            .fold(0, |mask, (i, _)| mask | 1 << i)
+    }
+
+    /// The tiles whose centers lie within the polygon with the given
+    /// corners, e.g. for area effects of arbitrary shape.
+    pub fn tiles_in_polygon(&self, poly: &[Point2<f32>]) -> Vec<C> {
+        if poly.is_empty() {
+            return Vec::new()
+        }
+        // The bounding box of the polygon.
+        let init = (Point2::new(f32::INFINITY, f32::INFINITY),
+                    Point2::new(f32::NEG_INFINITY, f32::NEG_INFINITY));
+        let (min, max) = poly.iter().fold(init, |(min, max), p| {
+            (Point2::new(min.x.min(p.x), min.y.min(p.y)),
+             Point2::new(max.x.max(p.x), max.y.max(p.y)))
+        });
+        let bounds = Bounds { position: min, width: max.x - min.x, height: max.y - min.y };
+        self.iter_within(&bounds)
+            .filter(|(_, h)| polygon_contains(poly, h.center))
+            .map(|(c, _)| *c)
+            .collect()
+    }
+
+    /// Flood-fill the grid from the given start coordinates, i.e. collect
+    /// the contiguous region of tiles around the start whose payloads
+    /// satisfy the given predicate. Tiles without payload are never part
+    /// of the region, and neither is the start if it does not satisfy the
+    /// predicate, in which case the region is empty.
+    pub fn flood<F>(&self, start: C, same: F) -> HashSet<C>
+    where F: Fn(&T) -> bool {
+        let matches = |c: C| matches!(self.payload(c), Some(t) if same(t));
+        if !matches(start) {
+            return HashSet::new()
+        }
+        Self::region(start, matches)
+    }
+
+    /// Group the tiles of the grid satisfying the given predicate, e.g.
+    /// the passable tiles, into connected components, i.e. maximal regions
+    /// of adjacent tiles, e.g. to detect unreachable parts of a map.
+    pub fn connected_components<F>(&self, passable: F) -> Vec<HashSet<C>>
+    where F: Fn(&C) -> bool {
+        let matches = |c: C| self.store.contains_key(&c) && passable(&c);
+        let mut components: Vec<HashSet<C>> = Vec::new();
+        for c in self.store.keys() {
+            if matches(*c) && !components.iter().any(|r| r.contains(c)) {
+                components.push(Self::region(*c, matches));
+            }
+        }
+        components
+    }
+
+    /// The contiguous region around the given start coordinates,
+    /// which are assumed to match the given predicate.
+    fn region<F>(start: C, matches: F) -> HashSet<C>
+    where F: Fn(C) -> bool {
+        let mut region = HashSet::new();
+        let mut fringe = vec![start];
+        region.insert(start);
+        while let Some(c) = fringe.pop() {
+            for n in coords::neighbours(c) {
+                if !region.contains(&n) && matches(n) {
+                    region.insert(n);
+                    fringe.push(n);
+                }
+            }
+        }
+        region
+    }
+
+    /// The tiles along the [`beeline`] between coordinates that
+    /// are part of the grid.
+    pub fn beeline(&self, from: C, to: C) -> impl Iterator<Item=(C, &Hexagon)> + '_ {
+        coords::beeline(from, to).filter_map(move |c| self.get(c).map(|h| (c, h)))
+    }
+
+    /// The tiles within the specified [`range`] of the given coordinates
+    /// that are part of the grid.
+    pub fn range(&self, c: C, r: u16) -> impl Iterator<Item=(C, &Hexagon)> + '_ {
+        coords::range(c, r).filter_map(move |c| self.get(c).map(|h| (c, h)))
+    }
+
+    /// The neighbours of the given coordinates that are part of the grid,
+    /// or an empty slice if the coordinates are not part of the grid.
+    ///
+    /// The neighbours of all tiles are computed once, on first use, and
+    /// retained for the lifetime of the grid, trading memory (roughly the
+    /// size of the grid itself) for faster lookups in hot loops, e.g. in
+    /// the [`Context::neighbours`](crate::search::Context::neighbours) of
+    /// a search on a large grid.
+    pub fn cached_neighbours(&self, c: C) -> &[C] {
+        let adjacency = self.adjacency.get_or_init(|| {
+            self.store.keys().map(|c| {
+                let ns = coords::neighbours(*c).filter(|n| self.store.contains_key(n)).collect();
+                (*c, ns)
+            }).collect()
+        });
+        adjacency.get(&c).map_or(&[], |ns| ns.as_slice())
+    }
+
+    /// The edges of the grid as a graph, i.e. every unordered pair of
+    /// adjacent tiles of the grid, yielded exactly once.
+    pub fn edges(&self) -> impl Iterator<Item=(C, C)> + '_ {
+        // Of every pair of opposite directions, only one is considered.
+        self.store.keys().flat_map(move |c| {
+            CubeVec::directions().take(3)
+                .map(move |v| (*c, C::from((*c).into() + v)))
+                .filter(move |(_, n)| self.store.contains_key(n))
+        })
+    }
+
+    /// The field of view of a viewer at the given coordinates, i.e. the
+    /// coordinates of the grid within the given radius that are visible
+    /// from the viewer, see [`range_visible`]. Coordinates outside of the
+    /// grid block the view.
+    pub fn fov<'a, F>(&'a self, viewer: C, radius: u16, transparent: F) -> impl Iterator<Item=C> + 'a
+    where F: Fn(C) -> bool + 'a {
+        coords::range_visible(viewer, radius, move |c| self.get(c).is_some() && transparent(c))
+            .filter(move |c| self.get(*c).is_some())
+    }
+
+    /// Update the fog of war for a single viewer at the given coordinates,
+    /// i.e. demote the previously visible coordinates to explored and reveal
+    /// the [`fov`](Grid::fov) of the viewer. For multiple viewers, [`Fog::hide`]
+    /// and reveal the field of view of every viewer instead.
+    ///
+    /// [`Fog::hide`]: fog::Fog::hide
+    pub fn update_visibility<F>(&self, fog: &mut fog::Fog<C>, viewer: C, radius: u16, transparent: F)
+    where F: Fn(C) -> bool {
+        fog.hide();
+        fog.reveal(self.fov(viewer, radius, transparent));
+    }
+
+    /// Cast a ray from the given pixel coordinates in the given (non-zero)
+    /// direction, yielding the tiles crossed by the ray, in order, together
+    /// with the indices of the edges through which the ray enters and exits
+    /// each tile. For the first tile, the entry edge is the edge through
+    /// which the ray would have entered had it started outside of the tile.
+    /// The ray stops at the boundary of the grid.
+    pub fn raycast(&self, from: Point2<f32>, dir: Vector2<f32>)
+        -> impl Iterator<Item=(C, usize, usize)> + '_
+    {
+        let mut next = self.from_pixel(from);
+        let mut t_exit = f32::NEG_INFINITY;
+        iter::from_fn(move || {
+            let (c, h) = next.take()?;
+            let cs = h.corners();
+            let hits = (0 .. 6)
+                .filter_map(|i| ray_intersection(from, dir, cs[i], cs[(i + 1) % 6]).map(|t| (i, t)))
+                .collect::<Vec<_>>();
+            let by_t = |a: &(usize, f32), b: &(usize, f32)| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal);
+            let (entry, _) = hits.iter().copied().min_by(by_t)?;
+            let (exit, t) = hits.iter().copied().max_by(by_t)?;
+            if t <= t_exit {
+                // The ray does not advance, e.g. it only touches a corner.
+                return None
+            }
+            t_exit = t;
+            // The next tile is the one containing a point just beyond
+            // the exit, which also resolves rays passing through corners.
+            let step = self.schema.side_len() * 0.01 / dir.norm();
+            let beyond = from + dir * (t + step);
+            next = self.from_pixel(beyond).filter(|(n, _)| *n != c);
+            Some((c, entry, exit))
+        })
+    }
+
     pub fn iter(&self) -> impl Iterator<Item=(&C, &Hexagon)> + '_ {
         self.store.iter()
     }
 
+    /// Iterate over the tiles of the grid in "painter's order", i.e. sorted
+    /// by the vertical and then horizontal pixel coordinates of the hexagon
+    /// centers. Drawing (overlapping or translucent) shapes in this order
+    /// yields deterministic results.
+    pub fn iter_painter(&self) -> impl Iterator<Item=(&C, &Hexagon)> + '_ {
+        let mut tiles = self.store.iter().collect::<Vec<_>>();
+        tiles.sort_by(|(_, a), (_, b)| {
+            a.center.y.partial_cmp(&b.center.y)
+                .unwrap_or(Ordering::Equal)
+                .then(a.center.x.partial_cmp(&b.center.x).unwrap_or(Ordering::Equal))
+        });
+        tiles.into_iter()
+    }
+
+    /// Iterate over the coordinates of the grid together with the
+    /// (offset-adjusted) pixel coordinates of the centers of their
+    /// hexagons, e.g. for placing sprites in bulk.
+    pub fn iter_pixels(&self) -> impl Iterator<Item=(C, Point2<f32>)> + '_ {
+        self.store.iter().map(|(c, hex)| (*c, hex.center))
+    }
+
     pub fn iter_within<'a>(&'a self, b: &'a Bounds)
         -> impl Iterator<Item=(&C, &Hexagon)> + 'a
     {
@@ -101,14 +563,154 @@ impl<C: Coords> Grid<C> {
                 b.intersects(&self.schema.bounds(&hex)))
     }
 
+    /// Iterate over the tiles within the given radius around the given
+    /// center coordinates in spiral order, i.e. beginning with the center
+    /// and walking the rings of increasing radius, skipping coordinates
+    /// that are not part of the grid. Useful e.g. for revealing a map
+    /// outward from a point.
+    pub fn spiral(&self, center: C, radius: u16) -> impl Iterator<Item=(C, &Hexagon)> + '_ {
+        coords::walk_range(center, FlatTopDirection::North, radius, Rotation::CW)
+            .filter_map(move |c| self.get(c).map(|h| (c, h)))
+    }
+
     pub fn dimensions(&self) -> &Dimensions {
         &self.dimensions
     }
 }
 
+/// The tile whose center is nearest to the given pixel coordinates among
+/// the given tiles, preferring the lowest `x` and then `y` cube coordinates
+/// among equally near tiles, for a result independent of the order of the
+/// tiles.
+fn nearest<'a, C: Coords + 'a>(
+    p: Point2<f32>,
+    tiles: impl Iterator<Item=(&'a C, &'a Hexagon)>
+) -> Option<(C, &'a Hexagon)> {
+    let key = |c: &C, h: &Hexagon| {
+        let cube = c.to_cube();
+        (nalgebra::distance_squared(&h.center, &p), cube.x(), cube.y())
+    };
+    tiles
+        .min_by(|(c1, h1), (c2, h2)| {
+            key(c1, h1).partial_cmp(&key(c2, h2)).unwrap_or(Ordering::Equal)
+        })
+        .map(|(c, h)| (*c, h))
+}
+
+/// The parameter `t` of the intersection of the ray `p + t * d` with
+/// the line segment between `a` and `b`, if any. Rays parallel to
+/// the line segment, including rays along the line segment, do not
+/// intersect it.
+fn ray_intersection(p: Point2<f32>, d: Vector2<f32>, a: Point2<f32>, b: Point2<f32>) -> Option<f32> {
+    const TOLERANCE: f32 = 1e-4;
+    let cross = |u: Vector2<f32>, v: Vector2<f32>| u.x * v.y - u.y * v.x;
+    let e = b - a;
+    let denom = cross(d, e);
+    if denom.abs() <= TOLERANCE * d.norm() * e.norm() {
+        return None
+    }
+    let w = a - p;
+    let s = cross(w, d) / denom;
+    if (-TOLERANCE ..= 1. + TOLERANCE).contains(&s) {
+        Some(cross(w, e) / denom)
+    } else {
+        None
+    }
+}
+
+/// The distance of a point to a line segment.
+fn segment_distance(p: Point2<f32>, a: Point2<f32>, b: Point2<f32>) -> f32 {
+    let ab = b - a;
+    let t = ((p - a).dot(&ab) / ab.norm_squared()).clamp(0., 1.);
+    nalgebra::distance(&p, &(a + ab * t))
+}
+
+impl<O: OffsetType, T> Grid<Offset<O>, T>
+where Offset<O>: Coords {
+    /// The extent of a rectangular grid in offset coordinates, as the
+    /// minimum and maximum columns and rows, i.e.
+    /// `(min_col, max_col, min_row, max_row)`. Yields `None` if the grid
+    /// is empty or not rectangular in the given offset coordinates.
+    pub fn extent(&self) -> Option<(i32, i32, i32, i32)> {
+        let mut keys = self.store.keys();
+        let first = keys.next()?;
+        let init = (first.col, first.col, first.row, first.row);
+        let (min_col, max_col, min_row, max_row) = keys.fold(init, |e, c| {
+            (e.0.min(c.col), e.1.max(c.col), e.2.min(c.row), e.3.max(c.row))
+        });
+        let cols = (max_col - min_col + 1) as usize;
+        let rows = (max_row - min_row + 1) as usize;
+        if cols * rows == self.store.len() {
+            Some((min_col, max_col, min_row, max_row))
+        } else {
+            None
+        }
+    }
+}
+
+/// Shorthands for grids with offset coordinates, which are commonly
+/// used for rectangular grids, addressing tiles by column and row.
+impl<O: OffsetType, T> Grid<Offset<O>, T>
+where Offset<O>: Coords {
+    /// Get the hexagon at the given column and row, if it is part
+    /// of the grid, i.e. `get(Offset::new(col, row))`.
+    pub fn get_rc(&self, col: i32, row: i32) -> Option<&Hexagon> {
+        self.get(Offset::new(col, row))
+    }
+
+    /// Whether the given column and row are part of the grid.
+    pub fn in_bounds_rc(&self, col: i32, row: i32) -> bool {
+        self.get_rc(col, row).is_some()
+    }
+}
+
+impl<C: Coords, T: PartialEq> Grid<C, T> {
+    /// Compute the changes to the payloads of this grid that yield the
+    /// payloads of the other grid, i.e. the coordinates whose payloads
+    /// differ, together with the payload in the other grid, whereby
+    /// `None` indicates that the payload was removed.
+    pub fn diff<'a>(&self, other: &'a Grid<C, T>) -> Vec<(C, Option<&'a T>)> {
+        let changed = other.payload.iter()
+            .filter(|(c, t)| self.payload.get(c) != Some(t))
+            .map(|(c, t)| (*c, Some(t)));
+        let removed = self.payload.keys()
+            .filter(|c| !other.payload.contains_key(c))
+            .map(|c| (*c, None));
+        changed.chain(removed).collect()
+    }
+}
+
+impl<C: Coords, T: Clone> Grid<C, T> {
+    /// Mirror the payloads of the grid across the given axis through
+    /// the origin, i.e. copy the payload of every tile to the tile with
+    /// the [reflected](Cube::reflect) coordinates, skipping coordinates
+    /// that are not part of the grid. Tiles without a payload are left
+    /// untouched, e.g. to generate one half of a symmetric map and
+    /// mirror it onto the other half.
+    pub fn mirror_into(&mut self, axis: Axis) {
+        let mirrored = self.payload.iter()
+            .map(|(c, t)| (c.to_cube().reflect(axis).as_::<C>(), t.clone()))
+            .filter(|(c, _)| self.store.contains_key(c))
+            .collect::<Vec<_>>();
+        for (c, t) in mirrored {
+            self.payload.insert(c, t);
+        }
+    }
+}
+
+impl<'a, C: Coords, T> IntoIterator for &'a Grid<C, T> {
+    type Item = (&'a C, &'a Hexagon);
+    type IntoIter = hash_map::Iter<'a, C, Hexagon>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.store.iter()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::grid::shape;
     use quickcheck::*;
 
     impl<C: Coords + Send + 'static> Arbitrary for Grid<C> {
@@ -138,5 +740,606 @@ mod tests {
         }
         quickcheck(prop as fn(_) -> _);
     }
+
+    #[test]
+    fn prop_closest() {
+        fn prop(g: Grid<Cube>) -> bool {
+            g.iter().all(|(c, h)| g.closest(h.center) == Some((*c, h)))
+        }
+        quickcheck(prop as fn(_) -> _);
+    }
+
+    #[test]
+    fn test_closest_outside() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid: Grid<Offset<OddCol>> = Grid::new(schema, shape::rectangle_xz_odd(5, 5));
+        let edge = grid.get(Offset::new(4, 2)).unwrap();
+        let p = Point2::new(grid.dimensions().width + 1., edge.center.y);
+        assert!(grid.from_pixel(p).is_none());
+        assert_eq!(grid.closest(p), Some((Offset::new(4, 2), edge)));
+    }
+
+    #[test]
+    fn test_closest_ties() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid: Grid<Cube> = Grid::new(schema.clone(), shape::rectangle_xz_odd(5, 5));
+        // Equidistant from the two tiles above and below, beyond the left
+        // edge of the grid, where both are at the same `x`.
+        let a = Cube::from(Offset::<OddCol>::new(0, 1));
+        let b = Cube::from(Offset::<OddCol>::new(0, 2));
+        let (ha, hb) = (grid.get(a).unwrap(), grid.get(b).unwrap());
+        let p = Point2::new(-20., (ha.center.y + hb.center.y) / 2.);
+        let expected = if (a.x(), a.y()) < (b.x(), b.y()) { a } else { b };
+        for _ in 0 .. 10 {
+            // Every grid iterates over its tiles in a different order.
+            let g: Grid<Cube> = Grid::new(schema.clone(), shape::rectangle_xz_odd(5, 5));
+            assert_eq!(g.closest(p).map(|(c, _)| c), Some(expected));
+            assert_eq!(g.pick_radius(p, 100.).map(|(c, _)| c), Some(expected));
+        }
+        // Points far away from any tile, e.g. in the corner of the bounding
+        // box of a hexagonal grid, still yield the closest tile.
+        let grid: Grid<Cube> = Grid::new(schema, shape::hexagon(4));
+        let corner = Point2::new(grid.dimensions().width, grid.dimensions().height) * 2.;
+        let closest = grid.iter()
+            .map(|(c, h)| (nalgebra::distance(&h.center, &corner), *c))
+            .fold((f32::INFINITY, None), |acc, (d, c)| if d < acc.0 { (d, Some(c)) } else { acc });
+        assert_eq!(grid.closest(corner).map(|(c, _)| c), closest.1);
+    }
+
+    #[test]
+    fn prop_iter_painter() {
+        fn prop(g: Grid<Cube>) -> bool {
+            let hexes = g.iter_painter().map(|(_, h)| h).collect::<Vec<_>>();
+            hexes.len() == g.iter().count() &&
+            hexes.windows(2).all(|w| {
+                w[0].center.y < w[1].center.y ||
+                w[0].center.y == w[1].center.y && w[0].center.x <= w[1].center.x
+            })
+        }
+        quickcheck(prop as fn(_) -> _);
+    }
+
+    #[test]
+    fn prop_reproject() {
+        fn prop(g: Grid<Offset<OddCol>>) -> bool {
+            let g2 = g.reproject::<Axial>().reproject::<Offset<OddCol>>();
+            g.iter().count() == g2.iter().count() &&
+            g.iter().all(|(c, h)| g2.get(*c) == Some(h)) &&
+            g.dimensions().pixel_offset == g2.dimensions().pixel_offset
+        }
+        quickcheck(prop as fn(_) -> _);
+    }
+
+    #[test]
+    fn prop_contains() {
+        fn prop(g: Grid<Cube>, c: Cube) -> bool {
+            g.iter().all(|(c, _)| g.contains(*c)) &&
+            g.contains(c) == g.get(c).is_some()
+        }
+        quickcheck(prop as fn(_,_) -> _);
+    }
+
+    #[test]
+    fn test_len() {
+        let schema = Schema::new(SideLength(10.), Orientation::PointyTop);
+        let shape = shape::rectangle_zx_odd(7, 9);
+        let total = shape.total;
+        let grid: Grid<Offset<OddRow>> = Grid::new(schema, shape);
+        assert_eq!(grid.len(), total);
+        assert_eq!((&grid).into_iter().count(), total);
+        assert!(!grid.is_empty());
+        assert!(grid.contains(Offset::new(0, 0)));
+        assert!(!grid.contains(Offset::new(-1, 0)));
+    }
+
+    #[test]
+    fn prop_range_beeline() {
+        fn prop(g: Grid<Cube>, c1: Cube, c2: Cube, r: u8) -> bool {
+            let r = r as u16 % 16;
+            g.range(c1, r).all(|(c, h)| g.get(c) == Some(h)) &&
+            g.range(c1, r).count() == coords::range(c1, r).filter(|c| g.contains(*c)).count() &&
+            g.beeline(c1, c2).all(|(c, h)| g.get(c) == Some(h))
+        }
+        quickcheck(prop as fn(_,_,_,_) -> _);
+    }
+
+    #[test]
+    fn test_range_edge() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid: Grid<Offset<OddCol>> = Grid::new(schema, shape::rectangle_xz_odd(5, 5));
+        let corner = Offset::new(0, 0);
+        let in_grid = grid.range(corner, 1).map(|(c, _)| c).collect::<Vec<_>>();
+        assert_eq!(coords::range(corner, 1).count(), 7);
+        assert_eq!(in_grid.len(), 3);
+        assert!(in_grid.iter().all(|c| c.col >= 0 && c.row >= 0));
+    }
+
+    #[test]
+    fn test_diff() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let mut g1: Grid<Offset<OddCol>, u8> = Grid::new(schema, shape::rectangle_xz_odd(5, 5));
+        let cs = g1.iter().map(|(c, _)| *c).collect::<Vec<_>>();
+        for c in &cs {
+            g1.set_payload(*c, 0);
+        }
+        let mut g2 = g1.clone();
+        assert!(g1.diff(&g2).is_empty());
+        g2.set_payload(Offset::new(1, 1), 1);
+        g2.set_payload(Offset::new(2, 3), 2);
+        g2.set_payload(Offset::new(4, 4), 0);
+        g2.remove_payload(Offset::new(0, 2));
+        assert_eq!(g2.set_payload(Offset::new(5, 5), 1), None);
+        let mut diff = g1.diff(&g2);
+        diff.sort_by_key(|(c, _)| (c.col, c.row));
+        assert_eq!(diff, vec![
+            (Offset::new(0, 2), None),
+            (Offset::new(1, 1), Some(&1)),
+            (Offset::new(2, 3), Some(&2)),
+        ]);
+    }
+
+    #[test]
+    fn test_apply_patch() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let mut base: Grid<Offset<OddCol>, u8> = Grid::new(schema, shape::rectangle_xz_odd(5, 5));
+        base.set_payload(Offset::new(0, 2), 3);
+        base.set_payload(Offset::new(1, 1), 0);
+        base.set_payload(Offset::new(4, 4), 0);
+        let mut target = base.clone();
+        target.set_payload(Offset::new(1, 1), 1);
+        target.set_payload(Offset::new(2, 3), 2);
+        target.remove_payload(Offset::new(0, 2));
+        let patch = base.diff(&target).into_iter().map(|(c, t)| (c, t.copied())).collect();
+        let mut patched = base.clone();
+        patched.apply_patch(patch);
+        assert!(patched.diff(&target).is_empty());
+        assert!(target.diff(&patched).is_empty());
+        // The inverse patch restores the base.
+        let undo = target.diff(&base).into_iter().map(|(c, t)| (c, t.copied())).collect();
+        patched.apply_patch(undo);
+        assert!(patched.diff(&base).is_empty());
+        assert!(base.diff(&patched).is_empty());
+    }
+
+    #[test]
+    fn test_from_pixel_within() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid: Grid<Offset<OddCol>> = Grid::new(schema, shape::rectangle_xz_odd(5, 5));
+        let edge = grid.get(Offset::new(0, 2)).unwrap();
+        let p = Point2::new(edge.corners()[3].x - 1., edge.center.y);
+        assert!(grid.from_pixel(p).is_none());
+        assert!(grid.from_pixel_within(p, 0.5).is_none());
+        assert_eq!(grid.from_pixel_within(p, 2.), Some((Offset::new(0, 2), edge)));
+        assert_eq!(grid.from_pixel_within(edge.center, 0.), Some((Offset::new(0, 2), edge)));
+    }
+
+    #[test]
+    fn test_extent() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid: Grid<Offset<OddCol>> = Grid::new(schema.clone(), shape::rectangle_xz_odd(30, 30));
+        assert_eq!(grid.extent(), Some((0, 29, 0, 29)));
+        let grid: Grid<Offset<OddCol>> = Grid::new(schema, shape::hexagon(3));
+        assert_eq!(grid.extent(), None);
+    }
+
+    /// Check that a pointy-top rectangular grid has exactly the offset
+    /// coordinates `(0..cols, 0..rows)`, that picking the center of every
+    /// hexagon yields its offset coordinates and that the rows are
+    /// staggered as indicated by `shift`, which is the horizontal
+    /// position of row 1 relative to row 0.
+    fn check_pointy_top_rectangle<T, I>(shape: Shape<I>, cols: i32, rows: i32, shift: f32)
+    where
+        T: OffsetType,
+        Offset<T>: Coords,
+        I: IntoIterator<Item=Cube>
+    {
+        let schema = Schema::new(SideLength(10.), Orientation::PointyTop);
+        let grid = Grid::<Offset<T>>::new(schema, shape);
+        let mut coords = grid.iter().map(|(c,_)| (c.col, c.row)).collect::<Vec<_>>();
+        coords.sort();
+        let expected = (0 .. cols)
+            .flat_map(|col| (0 .. rows).map(move |row| (col, row)))
+            .collect::<Vec<_>>();
+        assert_eq!(coords, expected);
+        for (c, h) in grid.iter() {
+            assert_eq!(grid.from_pixel(h.center), Some((*c, h)));
+            assert_eq!(grid.from_pixel(grid.to_pixel(*c)), Some((*c, h)));
+        }
+        let x0 = grid.to_pixel(Offset::<T>::new(0, 0)).x;
+        let x1 = grid.to_pixel(Offset::<T>::new(0, 1)).x;
+        let x2 = grid.to_pixel(Offset::<T>::new(0, 2)).x;
+        assert!((x1 - x0 - shift * grid.schema().width / 2.).abs() < 0.001);
+        assert!((x2 - x0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_pointy_top_rectangle_odd_row() {
+        check_pointy_top_rectangle::<OddRow, _>(shape::rectangle_zx_odd(7, 5), 5, 7, 1.);
+    }
+
+    #[test]
+    fn test_pointy_top_rectangle_even_row() {
+        check_pointy_top_rectangle::<EvenRow, _>(shape::rectangle_zx_even(7, 5), 5, 7, -1.);
+    }
+
+    #[test]
+    fn test_raycast_horizontal() {
+        let schema = Schema::new(SideLength(10.), Orientation::PointyTop);
+        let grid = Grid::<Offset<OddRow>>::new(schema, shape::rectangle_zx_odd(3, 5));
+        let from = grid.to_pixel(Offset::new(1, 2));
+        let tiles = grid.raycast(from, Vector2::new(1., 0.)).collect::<Vec<_>>();
+        let expected = (1 .. 5).map(|col| (Offset::new(col, 2), 3, 0)).collect::<Vec<_>>();
+        assert_eq!(tiles, expected);
+        assert!(tiles.iter().all(|(_, entry, exit)| (entry + 3) % 6 == *exit));
+        let tiles = grid.raycast(from, Vector2::new(-1., 0.)).collect::<Vec<_>>();
+        assert_eq!(tiles, vec![(Offset::new(1, 2), 0, 3), (Offset::new(0, 2), 0, 3)]);
+    }
+
+    #[test]
+    fn prop_raycast_adjacent() {
+        fn prop(g: Grid<Cube>, angle: u16) -> bool {
+            let angle = f32::from(angle % 360).to_radians();
+            let dir = Vector2::new(angle.cos(), angle.sin());
+            g.iter().take(8).all(|(c, h)| {
+                let tiles = g.raycast(h.center, dir).collect::<Vec<_>>();
+                tiles.first().map(|(c0, _, _)| c0) == Some(c) &&
+                tiles.windows(2).all(|w| coords::distance(w[0].0, w[1].0) == 1) &&
+                tiles.iter().all(|(_, entry, exit)| entry != exit)
+            })
+        }
+        quickcheck(prop as fn(_,_) -> _);
+    }
+
+    #[test]
+    fn prop_map_payload() {
+        fn prop(g: Grid<Cube>) -> bool {
+            let coords = g.iter().map(|(c,_)| *c).collect::<Vec<_>>();
+            let mut g: Grid<Cube, usize> = g.map_payload(|_, _| 0);
+            for (i, c) in coords.into_iter().enumerate().step_by(2) {
+                g.set_payload(c, i);
+            }
+            let h = g.map_payload(|c, i| (*c, i * 2));
+            h.len() == g.len() &&
+            h.dimensions().width == g.dimensions().width &&
+            h.dimensions().height == g.dimensions().height &&
+            g.iter().all(|(c, hex)| h.get(*c) == Some(hex)) &&
+            h.iter_payload().count() == g.iter_payload().count() &&
+            g.iter_payload().all(|(c, i)| h.payload(*c) == Some(&(*c, i * 2)))
+        }
+        quickcheck(prop as fn(_) -> _);
+    }
+
+    #[test]
+    fn test_neighbour_mask() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let mut grid = Grid::<Offset<OddCol>, bool>::new(schema, shape::rectangle_xz_odd(4, 4));
+        let corner = Offset::new(0, 0);
+        let dirs = [FlatTopDirection::SouthEast, FlatTopDirection::South];
+        let expected = dirs.iter().fold(0, |m, d| m | 1 << d.index() as u8);
+        assert_eq!(grid.neighbour_mask(corner), expected);
+        assert_eq!(grid.neighbour_mask(Offset::new(1, 1)), 0b111111);
+        grid.set_payload(Offset::new(0, 1), true);
+        let water = grid.neighbour_mask_where(corner, |t| t == Some(&true));
+        assert_eq!(water, 1 << FlatTopDirection::South.index() as u8);
+    }
+
+    #[test]
+    fn test_pick_detailed() {
+        let schema = Schema::new(SideLength(20.), Orientation::PointyTop);
+        let grid = Grid::<Cube>::new(schema, shape::hexagon(3));
+        let c = Cube::new_xz(1, 0);
+        let hex = grid.get(c).unwrap();
+        for i in 0 .. 6 {
+            // A point close to corner `i`, slightly closer to edge `i`.
+            let corner = hex.corners()[i];
+            let next = hex.corners()[(i + 1) % 6];
+            let p = corner + (hex.center - corner) * 0.1 + (next - corner) * 0.05;
+            let info = grid.pick_detailed(p).unwrap();
+            assert_eq!(info.coords, c);
+            assert_eq!(&info.hexagon, hex);
+            assert_eq!(info.corner, i);
+            assert_eq!(info.edge, i);
+            // The same near the edge `(i + 5) % 6`, also adjacent to the corner.
+            let prev = hex.corners()[(i + 5) % 6];
+            let p = corner + (hex.center - corner) * 0.1 + (prev - corner) * 0.05;
+            let info = grid.pick_detailed(p).unwrap();
+            assert_eq!(info.corner, i);
+            assert_eq!(info.edge, (i + 5) % 6);
+        }
+        assert!(grid.pick_detailed(Point2::new(-100., -100.)).is_none());
+    }
+
+    #[test]
+    fn test_flood() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let mut grid = Grid::<Cube, char>::new(schema, shape::hexagon(6));
+        let coords = grid.iter().map(|(c,_)| *c).collect::<Vec<_>>();
+        for c in coords {
+            let d = coords::distance(c, Cube::origin());
+            grid.set_payload(c, if d == 3 { '#' } else { '.' });
+        }
+        // The ring at distance 3 separates the inner from the outer region.
+        let inner = grid.flood(Cube::origin(), |t| *t == '.');
+        assert_eq!(inner, coords::range(Cube::origin(), 2).collect());
+        let outer = grid.flood(Cube::new_xz(5, 0), |t| *t == '.');
+        assert_eq!(outer.len(), grid.len() - coords::num_in_range(3));
+        assert!(outer.iter().all(|c| coords::distance(*c, Cube::origin()) > 3));
+        let ring = grid.flood(Cube::new_xz(3, 0), |t| *t == '#');
+        assert_eq!(ring.len(), coords::num_in_ring(3));
+        // Tiles without payload and non-matching starts are never filled.
+        grid.remove_payload(Cube::origin());
+        assert!(grid.flood(Cube::origin(), |t| *t == '.').is_empty());
+        assert!(!grid.flood(Cube::new_xz(1, 0), |t| *t == '.').contains(&Cube::origin()));
+    }
+
+    #[test]
+    fn test_tiles_in_polygon() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid = Grid::<Cube>::new(schema, shape::hexagon(6));
+        let (n, ne) = (FlatTopDirection::North.vector(), FlatTopDirection::NorthEast.vector());
+        // A triangle through the centers of three tiles, slightly enlarged
+        // to include the tiles whose centers lie on its edges.
+        let corners = [Cube::origin(), Cube::origin() + n * 3, Cube::origin() + ne * 3]
+            .iter().map(|c| grid.to_pixel(*c)).collect::<Vec<_>>();
+        let centroid = Point2::from((corners[0].coords + corners[1].coords + corners[2].coords) / 3.);
+        let triangle = corners.iter().map(|p| centroid + (p - centroid) * 1.01).collect::<Vec<_>>();
+        let mut tiles = grid.tiles_in_polygon(&triangle);
+        tiles.sort_by(Ord::cmp);
+        let mut expected = (0 ..= 3)
+            .flat_map(|a| (0 ..= 3 - a).map(move |b| Cube::origin() + n * a + ne * b))
+            .collect::<Vec<_>>();
+        expected.sort_by(Ord::cmp);
+        assert_eq!(tiles, expected);
+        assert!(grid.tiles_in_polygon(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_spiral() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid = Grid::<Cube>::new(schema, shape::hexagon(3));
+        let center = Cube::new_xz(2, 0);
+        let spiral = grid.spiral(center, 4).map(|(c,_)| c).collect::<Vec<_>>();
+        let dists = spiral.iter().map(|c| coords::distance(center, *c)).collect::<Vec<_>>();
+        assert!(dists.windows(2).all(|w| w[0] <= w[1]));
+        assert!(spiral.iter().all(|c| grid.get(*c).is_some()));
+        let expected = coords::range(center, 4)
+            .filter(|c| grid.get(*c).is_some())
+            .collect::<HashSet<_>>();
+        assert_eq!(spiral.len(), expected.len());
+        assert_eq!(spiral.into_iter().collect::<HashSet<_>>(), expected);
+        assert_eq!(grid.spiral(center, 0).count(), 1);
+        assert_eq!(grid.spiral(Cube::new_xz(10, 0), 2).count(), 0);
+    }
+
+    fn wrong_total() -> Shape<Vec<Cube>> {
+        let data = shape::hexagon(3).data.collect::<Vec<_>>();
+        Shape { data, total: 3 }
+    }
+
+    #[test]
+    fn test_new_exact_wrong_total() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid = Grid::<Cube>::new_exact(schema, wrong_total());
+        assert_eq!(grid.len(), coords::num_in_range(2));
+        assert!(coords::range(Cube::origin(), 2).all(|c| grid.get(c).is_some()));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic)]
+    fn test_new_wrong_total() {
+        // In release builds, the wrong total only affects preallocation.
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid = Grid::<Cube>::new(schema, wrong_total());
+        assert_eq!(grid.len(), coords::num_in_range(2));
+    }
+
+    #[test]
+    fn test_update_visibility() {
+        use fog::{ Fog, Visibility };
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid = Grid::<Cube>::new(schema, shape::hexagon(6));
+        let wall = Cube::new_xz(2, 0);
+        let behind = Cube::new_xz(4, 0);
+        let transparent = move |c: Cube| c != wall;
+        let mut fog = Fog::new();
+        grid.update_visibility(&mut fog, Cube::origin(), 4, transparent);
+        assert_eq!(fog.get(Cube::origin()), Visibility::Visible);
+        assert_eq!(fog.get(Cube::new_xz(1, 0)), Visibility::Visible);
+        assert_eq!(fog.get(wall), Visibility::Visible);
+        assert_eq!(fog.get(behind), Visibility::Hidden);
+        assert_eq!(fog.get(Cube::new_xz(-4, 0)), Visibility::Visible);
+        assert_eq!(fog.get(Cube::new_xz(-5, 0)), Visibility::Hidden);
+        assert!(fog.visible().all(|c| grid.get(*c).is_some()));
+        // Moving the viewer demotes the tiles no longer in sight.
+        grid.update_visibility(&mut fog, Cube::new_xz(5, 0), 1, transparent);
+        assert_eq!(fog.get(Cube::origin()), Visibility::Explored);
+        assert_eq!(fog.get(behind), Visibility::Visible);
+        assert_eq!(fog.get(Cube::new_xz(-5, 0)), Visibility::Hidden);
+    }
+
+    #[test]
+    fn test_edges() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid = Grid::<Cube>::new_exact(schema.clone(), Shape {
+            data: vec![Cube::origin(), Cube::new_xz(1, 0)],
+            total: 2,
+        });
+        assert_eq!(grid.edges().count(), 1);
+        let grid = Grid::<Cube>::new(schema, shape::hexagon(3));
+        let edges = grid.edges().collect::<Vec<_>>();
+        // Every tile has as many edges as neighbours in the grid.
+        let degrees = grid.iter()
+            .map(|(c,_)| coords::neighbours(*c).filter(|n| grid.get(*n).is_some()).count())
+            .sum::<usize>();
+        assert_eq!(edges.len() * 2, degrees);
+        assert!(edges.iter().all(|(a, b)| coords::distance(*a, *b) == 1));
+        let unordered = edges.iter()
+            .map(|(a, b)| if a.cmp(b) == Ordering::Less { (*a, *b) } else { (*b, *a) })
+            .collect::<HashSet<_>>();
+        assert_eq!(unordered.len(), edges.len());
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid = Grid::<Cube>::new(schema, shape::hexagon(5));
+        // A wall along the x-axis splits the grid in two halves.
+        let wall = |c: &Cube| c.z() == 0;
+        let components = grid.connected_components(|c| !wall(c));
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].len(), components[1].len());
+        assert_eq!(components[0].len() + components[1].len() + 9, grid.len());
+        assert!(components.iter().all(|r| r.iter().all(|c| !wall(c))));
+        assert_eq!(grid.connected_components(|_| true).len(), 1);
+        assert!(grid.connected_components(|_| false).is_empty());
+    }
+
+    #[test]
+    fn prop_cached_neighbours() {
+        fn prop(g: Grid<Cube>, c: Cube) -> bool {
+            let computed = |c: Cube| coords::neighbours(c)
+                .filter(|n| g.get(*n).is_some())
+                .collect::<Vec<_>>();
+            let c = Cube::new_xz(c.x() % 10, c.z() % 10);
+            g.iter().all(|(c,_)| g.cached_neighbours(*c) == computed(*c).as_slice()) &&
+            (g.get(c).is_some() || g.cached_neighbours(c).is_empty())
+        }
+        quickcheck(prop as fn(_,_) -> _);
+    }
+
+    #[test]
+    fn test_get_rc() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid = Grid::<Offset<OddCol>>::new(schema, shape::rectangle_xz_odd(5, 4));
+        for col in -1 ..= 5 {
+            for row in -1 ..= 4 {
+                assert_eq!(grid.get_rc(col, row), grid.get(Offset::new(col, row)));
+                let in_bounds = (0 .. 5).contains(&col) && (0 .. 4).contains(&row);
+                assert_eq!(grid.in_bounds_rc(col, row), in_bounds);
+            }
+        }
+    }
+
+    #[test]
+    fn test_center_coord() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid = Grid::<Offset<OddCol>>::new(schema.clone(), shape::hexagon(4));
+        assert_eq!(grid.center_coord(), Some(Offset::from(Cube::origin())));
+        let grid = Grid::<Offset<OddCol>>::new(schema.clone(), shape::rectangle_xz_odd(5, 5));
+        assert_eq!(grid.center_coord(), Some(Offset::new(2, 2)));
+        let shifted = shape::hexagon(4).data.map(|c| c + CubeVec::new_xz(3, -2)).collect::<Vec<_>>();
+        let grid = Grid::<Cube>::new_exact(schema, Shape { data: shifted, total: 0 });
+        assert_eq!(grid.center_coord(), Some(Cube::new_xz(3, -2)));
+    }
+
+    #[test]
+    fn test_empty_grid() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid = Grid::<Cube>::new(schema, Shape { data: Vec::new(), total: 0 });
+        assert!(grid.is_empty());
+        assert_eq!(grid.len(), 0);
+        let dim = grid.dimensions();
+        assert_eq!((dim.width, dim.height), (0., 0.));
+        assert_eq!(dim.pixel_offset, Vector2::zeros());
+        for p in &[Point2::origin(), Point2::new(5., 5.), Point2::new(-5., 20.)] {
+            assert!(grid.from_pixel(*p).is_none());
+            assert!(grid.closest(*p).is_none());
+        }
+        assert!(grid.get(Cube::origin()).is_none());
+        assert!(grid.center_coord().is_none());
+        assert_eq!(grid.iter().count(), 0);
+        assert_eq!(grid.edges().count(), 0);
+    }
+
+    #[test]
+    fn test_iter_pixels() {
+        let schema = Schema::new(SideLength(10.), Orientation::PointyTop);
+        let grid = Grid::<Offset<OddRow>>::new(schema, shape::rectangle_zx_odd(4, 3));
+        assert_eq!(grid.iter_pixels().count(), grid.len());
+        for (c, p) in grid.iter_pixels() {
+            assert_eq!(grid.get(c).map(|h| h.center), Some(p));
+            assert!((grid.to_pixel(c) - p).norm() < 0.001);
+        }
+    }
+
+    #[test]
+    fn test_mirror_into() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let mut grid = Grid::<Axial, i32>::new(schema, shape::hexagon(4));
+        let half = grid.iter().map(|(c, _)| *c)
+            .filter(|c| { let c = c.to_cube(); c.y() > c.z() })
+            .collect::<Vec<_>>();
+        for (i, c) in half.iter().enumerate() {
+            grid.set_payload(*c, i as i32);
+        }
+        grid.mirror_into(Axis::X);
+        for (i, c) in half.iter().enumerate() {
+            let r = c.to_cube().reflect(Axis::X).as_::<Axial>();
+            assert_eq!(grid.payload(r), Some(&(i as i32)));
+            assert_eq!(grid.payload(*c), Some(&(i as i32)));
+        }
+        // Tiles on the axis have no payload.
+        assert_eq!(grid.payload(Axial::from(Cube::new_xz(2, -1))), None);
+        assert_eq!(grid.iter_payload().count(), 2 * half.len());
+    }
+
+    #[test]
+    fn test_set_schema() {
+        let flat = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let pointy = Schema::new(SideLength(25.), Orientation::PointyTop);
+        let mut grid = Grid::<Offset<OddCol>, u8>::new(flat, shape::rectangle_xz_odd(6, 4));
+        grid.set_payload(Offset::new(2, 3), 7);
+        let keys = grid.iter().map(|(c, _)| *c).collect::<HashSet<_>>();
+        grid.set_schema(pointy.clone());
+        let expected = Grid::<Offset<OddCol>>::new(pointy.clone(), shape::rectangle_xz_odd(6, 4));
+        assert_eq!(grid.schema().orientation, Orientation::PointyTop);
+        assert_eq!(grid.iter().map(|(c, _)| *c).collect::<HashSet<_>>(), keys);
+        assert!(expected.iter().all(|(c, h)| grid.get(*c).unwrap().approx_eq(h, 0.001)));
+        assert_eq!(grid.dimensions().width, expected.dimensions().width);
+        assert_eq!(grid.dimensions().height, expected.dimensions().height);
+        assert_eq!(grid.payload(Offset::new(2, 3)), Some(&7));
+    }
+
+    #[test]
+    fn test_pick_radius() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid = Grid::<Cube>::new(schema, shape::hexagon(2));
+        let c = Cube::new_xz(1, 0);
+        let center = grid.to_pixel(c);
+        // A tap slightly off the center, within the radius.
+        let tap = center + Vector2::new(3., -4.);
+        assert_eq!(grid.pick_radius(tap, 6.).map(|(c, _)| c), Some(c));
+        assert_eq!(grid.pick_radius(tap, 4.), None);
+        // A tap outside of the grid, but within the radius of a tile.
+        let (edge, hex) = grid.iter().max_by(|(_, a), (_, b)|
+            a.center.x.partial_cmp(&b.center.x).unwrap()).unwrap();
+        let outside = hex.center + Vector2::new(12., 0.);
+        assert!(grid.from_pixel(outside).is_none());
+        assert_eq!(grid.pick_radius(outside, 15.).map(|(c, _)| c), Some(*edge));
+        assert_eq!(grid.pick_radius(outside, 11.), None);
+    }
+
+    #[test]
+    fn test_insert_shape() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let mut grid = Grid::<Cube, u8>::new(schema.clone(), shape::parallelogram_xz(2, 2));
+        grid.set_payload(Cube::origin(), 1);
+        assert_eq!(grid.cached_neighbours(Cube::new_xz(1, 1)).len(), 2);
+        let before = grid.iter().map(|(c, _)| *c).collect::<HashSet<_>>();
+        let region = shape::hexagon(3).data.map(|c| c + CubeVec::new_xz(4, -2)).collect::<HashSet<_>>();
+        grid.insert_shape(region.iter().copied());
+        let after = grid.iter().map(|(c, _)| *c).collect::<HashSet<_>>();
+        assert_eq!(after, before.union(&region).copied().collect());
+        assert_eq!(grid.len(), before.len() + region.len() - before.intersection(&region).count());
+        assert_eq!(grid.payload(Cube::origin()), Some(&1));
+        // The geometry is that of a grid created with all tiles at once.
+        let all = after.iter().copied().collect::<Vec<_>>();
+        let expected = Grid::<Cube>::new_exact(schema, Shape { data: all, total: 0 });
+        assert_eq!(grid.dimensions().width, expected.dimensions().width);
+        assert_eq!(grid.dimensions().height, expected.dimensions().height);
+        assert!(expected.iter().all(|(c, h)| grid.get(*c) == Some(h)));
+        // The cached adjacency is invalidated.
+        assert_eq!(grid.cached_neighbours(Cube::new_xz(1, 1)).len(), 3);
+    }
 }
 
@@ -9,16 +9,38 @@ pub use offset::*;
 
 use crate::geo;
 
-use std::collections::HashSet;
+use std::collections::{ HashMap, HashSet };
 use std::cmp::{ min, max };
 use std::fmt::{ Debug, Display };
 use std::hash::Hash;
 use std::iter;
 
-/// TODO
+/// Coordinates of a hexagonal grid, convertible from and into cube
+/// coordinates, on which all algorithms of this module operate.
 pub trait Coords:
     From<Cube> + Into<Cube> + Eq + Copy + Debug + Display + Hash
-{}
+{
+    /// Convert into cube coordinates, which avoids type annotations
+    /// for conversions via `Into<Cube>` in generic code.
+    fn to_cube(self) -> Cube {
+        self.into()
+    }
+
+    /// The neighbouring coordinates, see [`neighbours`].
+    fn neighbours(self) -> impl Iterator<Item=Self> {
+        neighbours(self)
+    }
+
+    /// The distance to other coordinates, see [`distance`].
+    fn distance(self, other: Self) -> usize {
+        distance(self, other)
+    }
+
+    /// The coordinates within the given range, see [`range`].
+    fn range(self, r: u16) -> impl Iterator<Item=Self> + Clone {
+        range(self, r)
+    }
+}
 
 /// Iterate over the neighbouring (adjacent) coordinates.
 pub fn neighbours<C>(c: C) -> impl Iterator<Item=C>
@@ -36,6 +58,16 @@ where
     CubeVec::diagonals().map(move |v| C::from(c.into() + v))
 }
 
+/// Iterate over the coordinates within a distance of 2, excluding the
+/// given coordinates, i.e. the neighbours and their neighbours, e.g. for
+/// splash effects. See [`ring`] for the coordinates at distance 2 only.
+pub fn extended_neighbours<C>(c: C) -> impl Iterator<Item=C>
+where
+    C: Coords
+{
+    range(c, 2).filter(move |n| *n != c)
+}
+
 /// The (beeline) distance between coordinates.
 pub fn distance<C>(from: C, to: C) -> usize
 where
@@ -43,9 +75,7 @@ where
 {
     let a: Cube = from.into();
     let b: Cube = to.into();
-    ( (a.p.x - b.p.x).abs() as usize +
-      (a.p.y - b.p.y).abs() as usize +
-      (a.p.z - b.p.z).abs() as usize ) / 2
+    (a - b).length()
 }
 
 /// The shortest path to other coordinates along a straight line,
@@ -62,6 +92,25 @@ where
     }
 }
 
+/// The prefix of the [`beeline`] between coordinates, up to and including
+/// the first coordinates that are blocked according to the given predicate.
+/// The start coordinates are never considered blocked. If no coordinates
+/// on the beeline are blocked, the full beeline is returned.
+pub fn beeline_until<C, F>(from: C, to: C, blocked: F) -> Vec<C>
+where
+    C: Coords,
+    F: Fn(C) -> bool
+{
+    let mut line = Vec::with_capacity(distance(from, to) + 1);
+    for c in beeline(from, to) {
+        line.push(c);
+        if c != from && blocked(c) {
+            break
+        }
+    }
+    line
+}
+
 /// TODO
 pub struct LineIterator<C> {
     distance: usize,
@@ -95,6 +144,9 @@ where
 
 impl<C: Coords> ExactSizeIterator for LineIterator<C> {}
 
+/// Linear interpolation between coordinates, rounded to the nearest
+/// coordinates, yielding `from` for `t = 0` and `to` for `t = 1`.
+/// See also [`Cube::lerp`].
 pub fn lerp<C>(from: C, to: C, t: geo::Frac1) -> C
 where
     C: Coords
@@ -104,17 +156,44 @@ where
     let x = geo::lerp(a.x(), b.x(), t);
     let y = geo::lerp(a.y(), b.y(), t);
     let z = geo::lerp(a.z(), b.z(), t);
-    C::from(Cube::round(x, y, z))
+    C::from(round(x, y, z))
 }
 
-/// The number of coordinates that are within the given range.
+/// Round fractional cube coordinates, i.e. a point `(x, y, z)` with
+/// `x + y + z = 0`, to the nearest cube coordinates, e.g. for custom
+/// interpolation between coordinates.
+pub fn round(x: f32, y: f32, z: f32) -> Cube {
+    Cube::round(x, y, z)
+}
+
+/// The number of coordinates that are within the given range, i.e.
+/// the number of coordinates yielded by [`range`], which is the sum
+/// of the [`ring_sizes`] up to that range: `3r(r+1) + 1`.
 pub fn num_in_range(r: u16) -> usize {
-    num_in_ring(r) * (r as usize + 1) / 2 + 1
+    let r = r as usize;
+    3 * r * (r + 1) + 1
+}
+
+/// The sizes of the rings of radius `0` up to and including `max_r`,
+/// as per [`num_in_ring`], e.g. for tuning reveal radii.
+pub fn ring_sizes(max_r: u16) -> Vec<usize> {
+    (0 ..= max_r).map(num_in_ring).collect()
 }
 
 /// The number of coordinates that are in a ring of a given radius.
+/// The ring of radius `0` consists of the center only.
 pub fn num_in_ring(r: u16) -> usize {
-    6 * (r as usize)
+    if r == 0 { 1 } else { 6 * (r as usize) }
+}
+
+/// The coordinates at exactly the given distance from the given
+/// coordinates, i.e. the ring of the given radius around them, in
+/// no particular order. See [`walk_ring`] for walking along the ring.
+pub fn ring<C>(c: C, r: u16) -> impl Iterator<Item=C>
+where
+    C: Coords
+{
+    range(c, r).filter(move |x| distance(c, *x) == r as usize)
 }
 
 /// The coordinates that are within the specified range of the given
@@ -181,6 +260,87 @@ where
     reachable
 }
 
+/// The coordinates that are reachable from the given coordinates within
+/// the given number of steps, grouped by the number of steps needed to
+/// reach them, i.e. the `i`-th group contains the coordinates first reached
+/// after `i` steps. The first group always contains only the start
+/// coordinates and the last group is never empty. Only coordinates
+/// satisfying the given predicate are considered passable.
+pub fn reachable_rings<C, F>(c: C, max_steps: u16, passable: F) -> Vec<Vec<C>>
+where
+    C: Coords,
+    F: Fn(C) -> bool
+{
+    let mut reachable = HashSet::new();
+    let mut rings = Vec::with_capacity(max_steps as usize + 1);
+    reachable.insert(c);
+    rings.push(vec![c]);
+    for i in 0 .. max_steps as usize {
+        let mut ring = Vec::new();
+        for &c in &rings[i] {
+            for cn in neighbours(c) {
+                if !reachable.contains(&cn) && passable(cn) {
+                    reachable.insert(cn);
+                    ring.push(cn);
+                }
+            }
+        }
+        if ring.is_empty() {
+            break
+        }
+        rings.push(ring);
+    }
+    rings
+}
+
+/// An influence map of the given sources, e.g. for the AI, in which every
+/// source contributes `max(0, 1 - d * falloff)` to the coordinates within
+/// the given radius at distance `d` from the source, and contributions of
+/// multiple sources add up. Coordinates without any (positive) contribution
+/// are not contained in the map.
+pub fn influence<C, I>(sources: I, radius: u16, falloff: f32) -> HashMap<C, f32>
+where
+    C: Coords,
+    I: IntoIterator<Item=C>
+{
+    let mut map = HashMap::new();
+    for s in sources {
+        for c in range(s, radius) {
+            let value = 1. - distance(s, c) as f32 * falloff;
+            if value > 0. {
+                *map.entry(c).or_insert(0.) += value;
+            }
+        }
+    }
+    map
+}
+
+/// The difference between two sets of coordinates, e.g. the ranges
+/// of a unit before and after a movement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RangeDelta<C: Coords> {
+    /// The coordinates in the new set that are not in the old set.
+    pub entered: HashSet<C>,
+    /// The coordinates in the old set that are not in the new set.
+    pub left: HashSet<C>,
+}
+
+impl<C: Coords> RangeDelta<C> {
+    /// Compute the coordinates that entered and left a range,
+    /// given the old and the new range.
+    pub fn new(old: &HashSet<C>, new: &HashSet<C>) -> RangeDelta<C> {
+        RangeDelta {
+            entered: new.difference(old).copied().collect(),
+            left: old.difference(new).copied().collect(),
+        }
+    }
+
+    /// Whether the range did not change.
+    pub fn is_empty(&self) -> bool {
+        self.entered.is_empty() && self.left.is_empty()
+    }
+}
+
 /// Returns an iterator over the visible coordinates in the specified range.
 ///
 /// Visibility of a coordinate `c` is determined by checking
@@ -199,10 +359,32 @@ where
     })
 }
 
+/// Returns an iterator over the visible coordinates in the specified range,
+/// taking partial cover into account.
+///
+/// Visibility of a coordinate `x` is determined by accumulating the
+/// opacity of all coordinates between `c` and `x` (as determined by
+/// [`beeline`]), whereby `x` is visible if the accumulated opacity is
+/// less than `1.0`. Thus coordinates with an opacity of `1.0` are fully
+/// blocking, as for [`range_visible`], whereas e.g. two consecutive
+/// coordinates with an opacity of `0.5` jointly block visibility.
+pub fn range_visible_soft<C, F>(c: C, r: u16, opacity: F) -> impl Iterator<Item=C>
+where
+    C: Coords,
+    F: Fn(C) -> f32
+{
+    range(c, r).filter(move |x| {
+        let l = beeline(c, *x);
+        let n = l.len(); // n > 0
+        l.take(n - 1).map(&opacity).sum::<f32>() < 1.0
+    })
+}
+
 /// Iterate over the coordinates in the ring at a given distance
 /// from `self`, starting at the first coordinate of the ring in
 /// the given direction from `self` and walking along the ring
-/// as per the given `Rotation`.
+/// as per the given `Rotation`. Like for [`ring`] and [`range`],
+/// the ring of radius `0` consists of `self` only.
 pub fn walk_ring<C, D>(c: C, dir: D, rad: u16, rot: geo::Rotation) -> impl Iterator<Item=C>
 where
     C: Coords,
@@ -237,7 +419,12 @@ where
 
     fn next(&mut self) -> Option<C> {
         if self.radius == 0 {
-            return None
+            // The ring of radius 0 is the center, visited once.
+            if self.dir_count > 0 {
+                return None
+            }
+            self.dir_count = 1;
+            return Some(self.pos)
         }
         if self.dir_count >= self.radius {
             self.dirs.next().and_then(|dir| {
@@ -254,9 +441,11 @@ where
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let remaining = {
-            self.dirs.len() as u16 * self.radius + self.radius - self.dir_count
-        } as usize;
+        let remaining = if self.radius == 0 {
+            1 - self.dir_count as usize
+        } else {
+            (self.dirs.len() as u16 * self.radius + self.radius - self.dir_count) as usize
+        };
         (remaining, Some(remaining))
     }
 }
@@ -1,5 +1,6 @@
 //! Axial coordinates.
 
+use std::cmp::Ordering;
 use std::fmt;
 use super::*;
 
@@ -22,6 +23,20 @@ impl fmt::Display for Axial {
     }
 }
 
+/// Axial coordinates are ordered consistently with the
+/// ordering of the corresponding cube coordinates.
+impl Ord for Axial {
+    fn cmp(&self, other: &Axial) -> Ordering {
+        Cube::from(*self).cmp(&Cube::from(*other))
+    }
+}
+
+impl PartialOrd for Axial {
+    fn partial_cmp(&self, other: &Axial) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl From<Cube> for Axial {
     fn from(c: Cube) -> Axial {
         Axial { col: c.x(), row: c.z() }
@@ -46,5 +61,28 @@ mod tests {
         }
         quickcheck(prop as fn(_)  -> _);
     }
+
+    #[test]
+    fn prop_ord_consistent_with_cube() {
+        fn prop(cs: Vec<Cube>) -> bool {
+            let mut axial = cs.iter().map(|c| Axial::from(*c)).collect::<Vec<_>>();
+            let mut cube = cs.clone();
+            axial.sort();
+            cube.sort_by(Ord::cmp);
+            axial.into_iter().map(Cube::from).collect::<Vec<_>>() == cube
+        }
+        quickcheck(prop as fn(_) -> _);
+    }
+
+    #[test]
+    fn prop_coords_methods() {
+        fn prop(c: Cube, d: Cube) -> bool {
+            let (a, b) = (Axial::from(c), Axial::from(d));
+            a.neighbours().eq(neighbours(a)) &&
+            a.distance(b) == distance(c, d) &&
+            a.range(2).eq(range(a, 2))
+        }
+        quickcheck(prop as fn(_,_) -> _);
+    }
 }
 
@@ -48,6 +48,11 @@ impl Cube {
         Self::mk(0, 0, 0)
     }
 
+    /// Convert into other coordinates, the inverse of [`Coords::to_cube`].
+    pub fn as_<C: Coords>(self) -> C {
+        C::from(self)
+    }
+
     pub fn new_xz(x: i32, z: i32) -> Cube {
         Self::mk(x, -x - z, z)
     }
@@ -64,8 +69,20 @@ impl Cube {
     pub fn y(&self) -> i32 { self.p.y }
     pub fn z(&self) -> i32 { self.p.z }
 
-    /// Round to the nearest cube coordinate.
-    pub(crate) fn round(x: f32, y: f32, z: f32) -> Cube {
+    /// Reflect the coordinates across the given axis through the
+    /// origin, i.e. keep the coordinate of that axis and swap the
+    /// other two.
+    pub fn reflect(self, axis: Axis) -> Cube {
+        let (x, y, z) = (self.x(), self.y(), self.z());
+        match axis {
+            Axis::X => Cube::mk(x, z, y),
+            Axis::Y => Cube::mk(z, y, x),
+            Axis::Z => Cube::mk(y, x, z),
+        }
+    }
+
+    /// Round to the nearest cube coordinate, see [`coords::round`](super::round).
+    pub fn round(x: f32, y: f32, z: f32) -> Cube {
         debug_assert!((x + y + z) as isize == 0);
         let (rx, ry, rz) = (x.round(), y.round(), z.round());
         let (dx, dy, dz) = ((x - rx).abs(), (y - ry).abs(), (z - rz).abs());
@@ -80,6 +97,12 @@ impl Cube {
         }
     }
 
+    /// Linear interpolation towards the given coordinates, see
+    /// [`coords::lerp`](super::lerp).
+    pub fn lerp(self, other: Cube, t: geo::Frac1) -> Cube {
+        lerp(self, other, t)
+    }
+
     pub(crate) fn mk(x: i32, y: i32, z: i32) -> Cube {
         debug_assert!(x + y + z == 0);
         Cube { p: Point3::new(x, y, z) }
@@ -116,6 +139,10 @@ impl Cube {
 
 impl Coords for Cube {}
 
+/// The axes of cube coordinates, e.g. for reflections.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Axis { X, Y, Z }
+
 impl fmt::Display for Cube {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "({},{},{})", self.p.x, self.p.y, self.p.z)
@@ -177,6 +204,7 @@ mod tests {
     use super::*;
     use quickcheck::*;
     use rand::{ Rng, thread_rng };
+    use rand::seq::SliceRandom;
     use std::cmp::max;
     use std::collections::HashSet;
     use std::i32;
@@ -188,6 +216,12 @@ mod tests {
         }
     }
 
+    impl Arbitrary for Axis {
+        fn arbitrary<G: Gen>(g: &mut G) -> Axis {
+            *[Axis::X, Axis::Y, Axis::Z].choose(g).unwrap()
+        }
+    }
+
     impl Arbitrary for CubeVec {
         fn arbitrary<G: Gen>(g: &mut G) -> CubeVec {
             let (x, z) = (g.gen::<i16>(), g.gen::<i16>());
@@ -259,6 +293,27 @@ mod tests {
         quickcheck(prop as fn(_,_) -> _);
     }
 
+    #[test]
+    fn prop_beeline_until_blocked() {
+        fn prop(c: Cube, r: u16, d: FlatTopDirection) -> bool {
+            let len = (r % 32) as i32 + 2;
+            let end = c + d.vector() * len;
+            let wall = c + d.vector() * (len / 2);
+            let line = beeline_until(c, end, |x| x == wall);
+            line == beeline(c, end).take(len as usize / 2 + 1).collect::<Vec<_>>()
+                && line.last() == Some(&wall)
+        }
+        quickcheck(prop as fn(_,_,_) -> _);
+    }
+
+    #[test]
+    fn prop_beeline_until_unblocked() {
+        fn prop(c1: Cube, c2: Cube) -> bool {
+            beeline_until(c1, c2, |_| false) == beeline(c1, c2).collect::<Vec<_>>()
+        }
+        quickcheck(prop as fn(_,_) -> _);
+    }
+
     #[test]
     fn prop_cube_round_valid() {
         fn prop(xi: i16, dx: Frac1, yi: i16, dy: Frac1) -> bool {
@@ -324,6 +379,32 @@ mod tests {
         quickcheck(prop as fn(_,_,_,_) -> _);
     }
 
+    #[test]
+    fn prop_reachable_rings() {
+        fn prop(c: Cube, r: u16) -> bool {
+            let r = r % 16;
+            let rings = reachable_rings(c, r, |_| true);
+            rings.len() == r as usize + 1 &&
+            rings[0] == vec![c] &&
+            rings.iter().enumerate().skip(1).all(|(i, ring)| {
+                ring.iter().cloned().collect::<HashSet<_>>()
+                    ==
+                walk_ring(c, FlatTopDirection::North, i as u16, Rotation::CW).collect()
+            })
+        }
+        quickcheck(prop as fn(_,_) -> _);
+    }
+
+    #[test]
+    fn prop_reachable_rings_blocked() {
+        fn prop(c: Cube, r: u16) -> bool {
+            let r = r % 16 + 1;
+            let rings = reachable_rings(c, r, |x| distance(c, x) < 2);
+            rings.len() == 2 && rings[1].len() == 6
+        }
+        quickcheck(prop as fn(_,_) -> _);
+    }
+
     #[test]
     fn prop_cube_to_pixel_origin() {
         fn prop(o: Orientation, l: SideLength) -> bool {
@@ -342,6 +423,30 @@ mod tests {
         quickcheck(prop as fn(_,_,_) -> _);
     }
 
+    #[test]
+    fn prop_cube_from_to_pixel_stretched() {
+        fn prop(c: Cube, s: SideLength, o: Orientation) -> bool {
+            let s = Schema::new(s, o).stretch_y(0.75);
+            Cube::from_pixel(c.to_pixel(&s), &s) == c
+        }
+        quickcheck(prop as fn(_,_,_) -> _);
+    }
+
+    #[test]
+    fn prop_neighbour_offsets() {
+        fn prop(c: (i8, i8), s: SideLength, o: Orientation) -> bool {
+            let s = Schema::new(s, o);
+            let c = Cube::new_xz(c.0 as i32, c.1 as i32);
+            let center = c.to_pixel(&s);
+            s.neighbour_offsets().iter().zip(CubeVec::directions()).all(|(v, d)| {
+                let n = (c + d).to_pixel(&s);
+                let p = center + v;
+                (n.x - p.x).abs() < 0.01 && (n.y - p.y).abs() < 0.01
+            })
+        }
+        quickcheck(prop as fn(_,_,_) -> _);
+    }
+
     #[test]
     fn prop_range_visible_all() {
         fn prop(c: Cube, r: u16) -> bool {
@@ -352,6 +457,31 @@ mod tests {
         quickcheck(prop as fn(_,_) -> _);
     }
 
+    #[test]
+    fn prop_range_visible_soft() {
+        fn prop(c: Cube, d: FlatTopDirection) -> bool {
+            let (c1, c2, c3) = (c + d.vector(), c + d.vector() * 2, c + d.vector() * 3);
+            let one = |x| if x == c1 { 0.5 } else { 0. };
+            let two = |x| if x == c1 || x == c2 { 0.5 } else { 0. };
+            let visible_one = range_visible_soft(c, 3, one).collect::<HashSet<_>>();
+            let visible_two = range_visible_soft(c, 3, two).collect::<HashSet<_>>();
+            visible_one.contains(&c3) &&
+            visible_two.contains(&c2) && !visible_two.contains(&c3)
+        }
+        quickcheck(prop as fn(_,_) -> _);
+    }
+
+    #[test]
+    fn prop_range_visible_soft_hard() {
+        fn prop(c: Cube, r: u16, d: FlatTopDirection) -> bool {
+            let blocked = c + d.vector();
+            let hard = range_visible(c, r % 16, |x| x != blocked);
+            let soft = range_visible_soft(c, r % 16, |x| if x == blocked { 1. } else { 0. });
+            hard.collect::<HashSet<_>>() == soft.collect()
+        }
+        quickcheck(prop as fn(_,_,_) -> _);
+    }
+
     #[test]
     fn prop_range_visible_blocked_dir() {
         fn prop(c: Cube, r: u16, d: FlatTopDirection) -> bool {
@@ -369,5 +499,116 @@ mod tests {
         }
         quickcheck(prop as fn(_,_,_) -> _);
     }
+
+    #[test]
+    fn test_range_delta() {
+        let a = Cube::origin();
+        let b = Cube::new_xz(1, 0);
+        let old = range_reachable(a, 1, |_| true);
+        let new = range_reachable(b, 1, |_| true);
+        let delta = RangeDelta::new(&old, &new);
+        let entered = neighbours(b).filter(|c| distance(*c, a) == 2).collect::<HashSet<_>>();
+        let left = neighbours(a).filter(|c| distance(*c, b) == 2).collect::<HashSet<_>>();
+        assert_eq!(delta.entered.len(), 3);
+        assert_eq!(delta.left.len(), 3);
+        assert_eq!(delta.entered, entered);
+        assert_eq!(delta.left, left);
+        assert!(RangeDelta::new(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_influence() {
+        let a = Cube::origin();
+        let map = influence(vec![a], 4, 0.25);
+        // The contribution vanishes at distance 4.
+        assert_eq!(map.len(), num_in_range(3));
+        assert_eq!(map[&a], 1.);
+        for (c, v) in &map {
+            let d = distance(a, *c);
+            assert!(*v <= map[&a]);
+            assert!(d == 0 || neighbours(*c).any(|n| distance(a, n) < d && map[&n] > *v));
+        }
+        // Contributions add up.
+        let b = Cube::new_xz(2, 0);
+        let map2 = influence(vec![a, b], 4, 0.25);
+        assert_eq!(map2[&Cube::new_xz(1, 0)], 1.5);
+        assert_eq!(map2[&a], 1.5);
+    }
+
+    #[test]
+    fn prop_walk_ring_ring() {
+        fn prop(c: Cube, r: u16, d: FlatTopDirection, rot: Rotation) -> bool {
+            let r = r % 16;
+            walk_ring(c, d, r, rot).count() == num_in_ring(r) &&
+            walk_ring(c, d, r, rot).collect::<HashSet<_>>() == ring(c, r).collect() &&
+            ring(c, r).count() == num_in_ring(r)
+        }
+        quickcheck(prop as fn(_,_,_,_) -> _);
+    }
+
+    #[test]
+    fn test_ring_radius_zero() {
+        let c = Cube::new_xz(2, -1);
+        assert_eq!(walk_ring(c, FlatTopDirection::North, 0, Rotation::CW).collect::<Vec<_>>(), vec![c]);
+        assert_eq!(ring(c, 0).collect::<Vec<_>>(), vec![c]);
+        assert_eq!(range(c, 0).collect::<Vec<_>>(), vec![c]);
+        assert_eq!(num_in_ring(0), 1);
+        assert_eq!(num_in_range(0), 1);
+    }
+
+    #[test]
+    fn prop_ring_sizes() {
+        fn prop(c: Cube, r: u16) -> bool {
+            let r = r % 32;
+            let sizes = ring_sizes(r);
+            let total = sizes.iter().scan(0, |sum, n| { *sum += n; Some(*sum) });
+            sizes.len() == r as usize + 1 &&
+            total.zip(0..).all(|(sum, i)| sum == num_in_range(i)) &&
+            range(c, r).count() == num_in_range(r)
+        }
+        quickcheck(prop as fn(_,_) -> _);
+    }
+
+    #[test]
+    fn prop_reflect() {
+        fn prop(c: Cube, a: Axis) -> bool {
+            let r = c.reflect(a);
+            r.is_valid() &&
+            r.reflect(a) == c &&
+            distance(Cube::origin(), r) == distance(Cube::origin(), c)
+        }
+        quickcheck(prop as fn(_,_) -> _);
+    }
+
+    #[test]
+    fn prop_extended_neighbours() {
+        fn prop(c: Cube) -> bool {
+            let ns = extended_neighbours(c).collect::<HashSet<_>>();
+            let second = neighbours(c).flat_map(neighbours).filter(|n| *n != c).collect();
+            ns.len() == num_in_range(2) - 1 && ns.len() == 18 &&
+            ns == second &&
+            ring(c, 2).all(|n| ns.contains(&n))
+        }
+        quickcheck(prop as fn(_) -> _);
+    }
+
+    #[test]
+    fn prop_lerp_endpoints() {
+        fn prop(a: Cube, b: Cube, t: Frac1) -> bool {
+            let c = a.lerp(b, t);
+            a.lerp(b, Frac1::new(0., 1.)) == a &&
+            a.lerp(b, Frac1::new(1., 1.)) == b &&
+            c.is_valid() && c == crate::grid::coords::lerp(a, b, t) &&
+            distance(a, c) <= distance(a, b) && distance(c, b) <= distance(a, b)
+        }
+        quickcheck(prop as fn(_,_,_) -> _);
+    }
+
+    #[test]
+    fn test_round() {
+        assert_eq!(round(0.4, -0.3, -0.1), Cube::origin());
+        assert_eq!(round(1.1, -0.6, -0.5), Cube::new_xz(1, 0));
+        assert_eq!(round(1.1, -0.4, -0.7), Cube::new_xz(1, -1));
+    }
 }
 
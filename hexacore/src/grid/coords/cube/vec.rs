@@ -28,7 +28,7 @@ pub trait Direction: Copy + Clone {
 }
 
 /// A displacement of cube coordinates.
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
 pub struct CubeVec(pub(super) Vector3<i32>);
 
 impl CubeVec {
@@ -52,6 +52,13 @@ impl CubeVec {
         CubeVec(Vector3::from(CUBE_DIR_VECTORS[d.index() as usize]))
     }
 
+    /// The index of the direction of a unit vector, i.e. the inverse
+    /// of [`CubeVec::direction`], or `None` if the vector is not a
+    /// unit vector.
+    pub fn direction_index(&self) -> Option<Z6> {
+        CubeVec::directions().position(|v| v == *self).and_then(Z6::from_usize)
+    }
+
     pub fn diagonals() -> impl DoubleEndedIterator<Item=CubeVec> + Clone {
         CUBE_DIA_VECTORS.iter().map(|v| CubeVec(Vector3::from(*v)))
     }
@@ -73,6 +80,20 @@ impl CubeVec {
         }
     }
 
+    /// The length of the vector, i.e. the distance of the cube coordinates
+    /// it displaces by.
+    pub fn length(&self) -> usize {
+        ( self.0.x.unsigned_abs() +
+          self.0.y.unsigned_abs() +
+          self.0.z.unsigned_abs() ) as usize / 2
+    }
+
+    /// Whether the vector is a displacement to a neighbouring (adjacent)
+    /// cube coordinate.
+    pub fn is_unit(&self) -> bool {
+        self.length() == 1
+    }
+
     /// Rotate the vector `n` times by 60 degrees in the given direction.
     pub fn rotate(&self, r: Rotation, n: Z6) -> CubeVec {
         match r {
@@ -268,6 +289,12 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cube_vectors_length() {
+        assert!(CubeVec::directions().all(|v| v.length() == 1 && v.is_unit()));
+        assert!(CubeVec::diagonals().all(|v| v.length() == 2 && !v.is_unit()));
+    }
+
     #[test]
     fn prop_vec_rotate() {
         fn prop(v: CubeVec, z: Z6) -> bool {
@@ -1,5 +1,6 @@
 //! Offset coordinates.
 
+use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::Debug;
 use std::hash::Hash;
@@ -69,11 +70,49 @@ impl<T: OffsetType> Offset<T> {
     }
 }
 
+impl<T: OffsetType> Offset<T>
+where Offset<T>: From<Cube> + Into<Cube> {
+    /// Check that the conversion of the given cube coordinates into
+    /// offset coordinates of this type and back is the identity.
+    pub fn is_valid_roundtrip(c: Cube) -> bool {
+        Offset::<T>::from(c).into() == c
+    }
+
+    /// Convert the coordinates into offset coordinates of a different type
+    /// that identify the same hexagon, e.g. from `Offset<OddCol>` into
+    /// `Offset<EvenCol>`.
+    pub fn convert<U: OffsetType>(self) -> Offset<U>
+    where Offset<U>: From<Cube> {
+        let c: Cube = self.into();
+        Offset::from(c)
+    }
+}
+
+/// Offset coordinates are ordered consistently with the
+/// ordering of the corresponding cube coordinates.
+impl<T: OffsetType> Ord for Offset<T>
+where Offset<T>: Coords {
+    fn cmp(&self, other: &Offset<T>) -> Ordering {
+        let a: Cube = (*self).into();
+        let b: Cube = (*other).into();
+        a.cmp(&b)
+    }
+}
+
+impl<T: OffsetType> PartialOrd for Offset<T>
+where Offset<T>: Coords {
+    fn partial_cmp(&self, other: &Offset<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl From<Cube> for Offset<OddCol> {
     fn from(c: Cube) -> Self {
         let col = c.x();
         let row = c.z() + (col - (col & 1)) / 2;
-        Offset { col, row, _ty: PhantomData }
+        let o = Offset { col, row, _ty: PhantomData };
+        debug_assert!(Cube::from(o) == c);
+        o
     }
 }
 
@@ -88,7 +127,9 @@ impl From<Cube> for Offset<EvenCol> {
     fn from(c: Cube) -> Self {
         let col = c.x();
         let row = c.z() + (col + (col & 1)) / 2;
-        Offset { col, row, _ty: PhantomData }
+        let o = Offset { col, row, _ty: PhantomData };
+        debug_assert!(Cube::from(o) == c);
+        o
     }
 }
 
@@ -103,7 +144,9 @@ impl From<Cube> for Offset<OddRow> {
     fn from(c: Cube) -> Self {
         let row = c.z();
         let col = c.x() + (row - (row & 1)) / 2;
-        Offset { col, row, _ty: PhantomData }
+        let o = Offset { col, row, _ty: PhantomData };
+        debug_assert!(Cube::from(o) == c);
+        o
     }
 }
 
@@ -118,7 +161,9 @@ impl From<Cube> for Offset<EvenRow> {
     fn from(c: Cube) -> Self {
         let row = c.z();
         let col = c.x() + (row + (row & 1)) / 2;
-        Offset { col, row, _ty: PhantomData }
+        let o = Offset { col, row, _ty: PhantomData };
+        debug_assert!(Cube::from(o) == c);
+        o
     }
 }
 
@@ -140,6 +185,13 @@ mod tests {
     use super::*;
     use quickcheck::*;
 
+    impl<T: OffsetType> Arbitrary for Offset<T>
+    where Offset<T>: From<Cube> {
+        fn arbitrary<G: Gen>(g: &mut G) -> Offset<T> {
+            Offset::from(Cube::arbitrary(g))
+        }
+    }
+
     #[test]
     fn prop_from_to_cube_identity() {
         fn prop(c: Cube) -> bool {
@@ -150,5 +202,92 @@ mod tests {
         }
         quickcheck(prop as fn(_)  -> _);
     }
+
+    #[test]
+    fn test_roundtrip_negative() {
+        for x in -8 ..= 8 {
+            for z in -8 ..= 8 {
+                let c = Cube::new_xz(x, z);
+                assert!(Offset::<OddCol>::is_valid_roundtrip(c));
+                assert!(Offset::<OddRow>::is_valid_roundtrip(c));
+                assert!(Offset::<EvenCol>::is_valid_roundtrip(c));
+                assert!(Offset::<EvenRow>::is_valid_roundtrip(c));
+            }
+        }
+    }
+
+    #[test]
+    fn prop_ord_consistent_with_cube() {
+        fn sorted<T: OffsetType>(cs: &[Cube]) -> Vec<Cube>
+        where Offset<T>: Coords {
+            let mut offsets = cs.iter().map(|c| Offset::<T>::from(*c)).collect::<Vec<_>>();
+            offsets.sort();
+            offsets.into_iter().map(Into::into).collect()
+        }
+        fn prop(cs: Vec<Cube>) -> bool {
+            let mut cube = cs.clone();
+            cube.sort_by(Ord::cmp);
+            sorted::<OddCol>(&cs) == cube &&
+            sorted::<OddRow>(&cs) == cube &&
+            sorted::<EvenCol>(&cs) == cube &&
+            sorted::<EvenRow>(&cs) == cube
+        }
+        quickcheck(prop as fn(_) -> _);
+    }
+
+    #[test]
+    fn prop_coords_methods() {
+        fn prop(c: Cube, d: Cube) -> bool {
+            let (a, b) = (Offset::<EvenRow>::from(c), Offset::<EvenRow>::from(d));
+            a.neighbours().eq(neighbours(a)) &&
+            a.neighbours().all(|n| a.distance(n) == 1) &&
+            a.distance(b) == distance(c, d) &&
+            a.range(2).count() == num_in_range(2)
+        }
+        quickcheck(prop as fn(_,_) -> _);
+    }
+
+    #[test]
+    fn prop_convert_roundtrip() {
+        fn prop(c: Cube) -> bool {
+            let odd = Offset::<OddCol>::from(c);
+            let even = odd.convert::<EvenCol>();
+            even == Offset::<EvenCol>::from(c) &&
+            even.convert::<OddCol>() == odd &&
+            odd.convert::<OddRow>().convert::<EvenRow>().convert::<OddCol>() == odd
+        }
+        quickcheck(prop as fn(_) -> _);
+    }
+
+    #[test]
+    fn prop_to_cube_as_roundtrip() {
+        fn prop(c: Cube) -> bool {
+            fn roundtrip<C: Coords>(c: Cube) -> bool {
+                let x: C = c.as_();
+                x.to_cube() == c && x.to_cube().as_::<C>() == x
+            }
+            roundtrip::<Cube>(c) &&
+            roundtrip::<Axial>(c) &&
+            roundtrip::<Offset<OddRow>>(c) &&
+            roundtrip::<Offset<EvenRow>>(c) &&
+            roundtrip::<Offset<OddCol>>(c) &&
+            roundtrip::<Offset<EvenCol>>(c)
+        }
+        quickcheck(prop as fn(_) -> _);
+    }
+
+    #[test]
+    fn prop_neighbours_match_cube() {
+        fn matches<T: OffsetType>(o: Offset<T>) -> bool
+        where Offset<T>: Coords {
+            let c: Cube = o.into();
+            neighbours(o).map(Into::into).eq(neighbours(c)) &&
+            neighbours(o).all(|n| distance(o, n) == 1)
+        }
+        fn prop(a: Offset<OddCol>, b: Offset<OddRow>, c: Offset<EvenCol>, d: Offset<EvenRow>) -> bool {
+            matches(a) && matches(b) && matches(c) && matches(d)
+        }
+        quickcheck(prop as fn(_,_,_,_) -> _);
+    }
 }
 
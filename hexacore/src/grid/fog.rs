@@ -0,0 +1,77 @@
+//! Fog of war, i.e. the knowledge of a player about the tiles of a grid.
+
+use crate::grid::Coords;
+
+use std::collections::HashSet;
+
+/// The visibility of coordinates in the [`Fog`].
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Visibility {
+    /// The coordinates have never been visible.
+    Hidden,
+    /// The coordinates have been visible before, but are not visible now.
+    Explored,
+    /// The coordinates are currently visible.
+    Visible,
+}
+
+/// The fog of war, tracking which coordinates are currently visible
+/// and which have been explored, e.g. for a player. Initially, all
+/// coordinates are hidden.
+#[derive(Clone, Debug)]
+pub struct Fog<C: Coords> {
+    explored: HashSet<C>,
+    visible: HashSet<C>,
+}
+
+impl<C: Coords> Default for Fog<C> {
+    fn default() -> Fog<C> {
+        Fog::new()
+    }
+}
+
+impl<C: Coords> Fog<C> {
+    pub fn new() -> Fog<C> {
+        Fog {
+            explored: HashSet::new(),
+            visible: HashSet::new(),
+        }
+    }
+
+    /// The visibility of the given coordinates.
+    pub fn get(&self, c: C) -> Visibility {
+        if self.visible.contains(&c) {
+            Visibility::Visible
+        } else if self.explored.contains(&c) {
+            Visibility::Explored
+        } else {
+            Visibility::Hidden
+        }
+    }
+
+    /// Make the given coordinates visible (and thereby explored).
+    pub fn reveal<I>(&mut self, cs: I)
+    where I: IntoIterator<Item=C> {
+        for c in cs {
+            self.explored.insert(c);
+            self.visible.insert(c);
+        }
+    }
+
+    /// Demote all visible coordinates to explored, e.g. at the
+    /// beginning of a turn before revealing what is visible anew.
+    pub fn hide(&mut self) {
+        self.visible.clear();
+    }
+
+    /// Iterate over the currently visible coordinates.
+    pub fn visible(&self) -> impl Iterator<Item=&C> + '_ {
+        self.visible.iter()
+    }
+
+    /// Iterate over the explored coordinates, including
+    /// the currently visible coordinates.
+    pub fn explored(&self) -> impl Iterator<Item=&C> + '_ {
+        self.explored.iter()
+    }
+}
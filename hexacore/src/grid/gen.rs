@@ -0,0 +1,65 @@
+//! Procedural generation of grids, available with the `gen` feature.
+
+use crate::geo::Schema;
+use crate::grid::{ Coords, Cube, Grid };
+use crate::grid::shape::Shape;
+
+use rand::{ Rng, SeedableRng };
+use rand::rngs::StdRng;
+
+/// Generate a grid with the given schema and shape, whose tiles are randomly
+/// marked as passable (`true`) or impassable (`false`), such that every tile
+/// is impassable with a probability given by the `density`. The same seed
+/// always yields the same map.
+///
+/// The resulting grid can directly serve as the
+/// [`Context`](crate::search::Context) of a search.
+pub fn random_map<C, I>(schema: Schema, shape: Shape<I>, seed: u64, density: f32) -> Grid<C, bool>
+where
+    C: Coords,
+    I: IntoIterator<Item=Cube>
+{
+    // Visit the tiles in the order given by the shape, which is
+    // deterministic, unlike the iteration order of the grid.
+    let total = shape.total;
+    let cubes = shape.into_iter().collect::<Vec<_>>();
+    let mut grid = Grid::new(schema, Shape { data: cubes.clone(), total });
+    let mut rng = StdRng::seed_from_u64(seed);
+    for c in cubes {
+        grid.set_payload(C::from(c), rng.gen::<f32>() >= density);
+    }
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::{ Orientation, SideLength };
+    use crate::grid::shape;
+    use crate::search::astar;
+
+    fn map(seed: u64, density: f32) -> Grid<Cube, bool> {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        random_map(schema, shape::hexagon(10), seed, density)
+    }
+
+    #[test]
+    fn test_random_map_seed() {
+        let (a, b, c) = (map(42, 0.5), map(42, 0.5), map(43, 0.5));
+        assert_eq!(a.iter_payload().count(), a.len());
+        assert!(a.iter().all(|(k, _)| a.payload(*k) == b.payload(*k)));
+        assert!(a.iter().any(|(k, _)| a.payload(*k) != c.payload(*k)));
+    }
+
+    #[test]
+    fn test_random_map_context() {
+        let mut open = map(7, 0.);
+        assert!(open.iter_payload().all(|(_, p)| *p));
+        let (start, goal) = (Cube::origin(), Cube::new_xz(9, 0));
+        let path = astar::path(start, goal, &mut open).unwrap();
+        assert_eq!(path.total_cost(), 9);
+        let mut closed = map(7, 1.);
+        assert!(closed.iter_payload().all(|(_, p)| !*p));
+        assert!(astar::path(start, goal, &mut closed).is_none());
+    }
+}
@@ -19,6 +19,50 @@ impl<I: IntoIterator<Item=Cube>> IntoIterator for Shape<I> {
     }
 }
 
+/// An iterator over the coordinates of a shape with a known total,
+/// providing an exact `size_hint`.
+#[derive(Clone)]
+pub struct ShapeIter<I> {
+    inner: I,
+    remaining: usize,
+}
+
+impl<I: Iterator<Item=Cube>> Iterator for ShapeIter<I> {
+    type Item = Cube;
+
+    fn next(&mut self) -> Option<Cube> {
+        let next = self.inner.next();
+        if next.is_some() {
+            self.remaining = self.remaining.saturating_sub(1);
+        }
+        next
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<I: Iterator<Item=Cube>> ExactSizeIterator for ShapeIter<I> {}
+
+/// Create a shape from the given coordinates and their total number.
+fn exact<I: Iterator<Item=Cube>>(data: I, total: usize) -> Shape<ShapeIter<I>> {
+    Shape { data: ShapeIter { inner: data, remaining: total }, total }
+}
+
+/// The number of coordinates of a rectangle or parallelogram
+/// with the given side lengths, which may be negative.
+fn area(a: i32, b: i32) -> usize {
+    (a.max(0) * b.max(0)) as usize
+}
+
+/// The number of coordinates of a triangle with the given side
+/// length, which may be negative.
+fn triangle_area(a: i32) -> usize {
+    let a = a.max(0);
+    (a * (a + 1) / 2) as usize
+}
+
 //////////////////////////////////////////////////////////////////////////////
 // Hexagon
 
@@ -48,10 +92,10 @@ impl<I: IntoIterator<Item=Cube>> IntoIterator for Shape<I> {
 /// \       /
 ///  \_____/
 /// ```
-pub fn hexagon(side_length: u16) -> Shape<impl Iterator<Item=Cube>> {
+pub fn hexagon(side_length: u16) -> Shape<impl ExactSizeIterator<Item=Cube>> {
     assert!(side_length > 0, "side_length == 0");
     let data = coords::range(Cube::origin(), side_length - 1);
-    Shape { data, total: coords::num_in_range(side_length - 1) }
+    exact(data, coords::num_in_range(side_length - 1))
 }
 
 //////////////////////////////////////////////////////////////////////////////
@@ -79,11 +123,11 @@ pub fn hexagon(side_length: u16) -> Shape<impl Iterator<Item=Cube>> {
 ///  \   /
 ///   \./
 /// ```
-pub fn parallelogram_xy(rows: i32, cols: i32) -> Shape<impl Iterator<Item=Cube>> {
+pub fn parallelogram_xy(rows: i32, cols: i32) -> Shape<impl ExactSizeIterator<Item=Cube>> {
     let data = (0 .. cols).flat_map(move |x| {
         (0 .. rows).map(move |y| Cube::new_xy(x, y))
     });
-    Shape { data, total: (rows * cols) as usize }
+    exact(data, area(rows, cols))
 }
 
 /// A parallelogram created by walking along `+x/+z` cube coordinates.
@@ -106,11 +150,11 @@ pub fn parallelogram_xy(rows: i32, cols: i32) -> Shape<impl Iterator<Item=Cube>>
 ///   \    \
 ///    \____\
 /// ```
-pub fn parallelogram_xz(rows: i32, cols: i32) -> Shape<impl Iterator<Item=Cube>> {
+pub fn parallelogram_xz(rows: i32, cols: i32) -> Shape<impl ExactSizeIterator<Item=Cube>> {
     let data = (0 .. cols).flat_map(move |x| {
         (0 .. rows).map(move |z| Cube::new_xz(x, z))
     });
-    Shape { data, total: (rows * cols) as usize }
+    exact(data, area(rows, cols))
 }
 
 /// A parallelogram created by walking along `+y/+z` cube coordinates.
@@ -132,11 +176,11 @@ pub fn parallelogram_xz(rows: i32, cols: i32) -> Shape<impl Iterator<Item=Cube>>
 ///  /     /
 /// /_____/
 /// ```
-pub fn parallelogram_yz(dy: i32, dz: i32) -> Shape<impl Iterator<Item=Cube>> {
+pub fn parallelogram_yz(dy: i32, dz: i32) -> Shape<impl ExactSizeIterator<Item=Cube>> {
     let data = (0 .. dy).flat_map(move |y| {
         (0 .. dz).map(move |z| Cube::new_yz(y, z))
     });
-    Shape { data, total: (dy * dz) as usize }
+    exact(data, area(dy, dz))
 }
 
 //////////////////////////////////////////////////////////////////////////////
@@ -156,14 +200,14 @@ pub fn parallelogram_yz(dy: i32, dz: i32) -> Shape<impl Iterator<Item=Cube>> {
 /// ```
 ///
 /// Pointy-Top: Flat-top rotated 30 degrees counterclockwise.
-pub fn rectangle_xz_even(dx: i32, dz: i32) -> Shape<impl Iterator<Item=Cube>> {
+pub fn rectangle_xz_even(dx: i32, dz: i32) -> Shape<impl ExactSizeIterator<Item=Cube>> {
     let data = (0 .. dx).flat_map(move |x| {
         let x_offset = (x + 1) / 2;
         (-x_offset .. dz - x_offset).map(move |z| {
             Cube::new_xz(x, z)
         })
     });
-    Shape { data, total: (dx * dz) as usize }
+    exact(data, area(dx, dz))
 }
 
 /// A rectangle created by walking along `+x/-z..+z` cube coordinates.
@@ -182,14 +226,14 @@ pub fn rectangle_xz_even(dx: i32, dz: i32) -> Shape<impl Iterator<Item=Cube>> {
 /// ```
 ///
 /// Pointy-Top: Flat-top rotated 30 degrees counterclockwise.
-pub fn rectangle_xz_odd(dx: i32, dz: i32) -> Shape<impl Iterator<Item=Cube>> {
+pub fn rectangle_xz_odd(dx: i32, dz: i32) -> Shape<impl ExactSizeIterator<Item=Cube>> {
     let data = (0 .. dx).flat_map(move |x| {
         let x_offset = x / 2;
         (-x_offset .. dz - x_offset).map(move |z| {
             Cube::new_xz(x,z)
         })
     });
-    Shape { data, total: (dx * dz) as usize }
+    exact(data, area(dx, dz))
 }
 
 /// A rectangle created by walking along `+z/-x..+x` cube coordinates.
@@ -207,14 +251,14 @@ pub fn rectangle_xz_odd(dx: i32, dz: i32) -> Shape<impl Iterator<Item=Cube>> {
 /// (2) >       >
 /// (3) |_______|
 /// ```
-pub fn rectangle_zx_even(dz: i32, dx: i32) -> Shape<impl Iterator<Item=Cube>> {
+pub fn rectangle_zx_even(dz: i32, dx: i32) -> Shape<impl ExactSizeIterator<Item=Cube>> {
     let data = (0 .. dz).flat_map(move |z| {
         let z_offset = (z + 1) / 2;
         (-z_offset .. dx - z_offset).map(move |x| {
             Cube::new_xz(x, z)
         })
     });
-    Shape { data, total: (dz * dx) as usize }
+    exact(data, area(dz, dx))
 }
 
 /// A rectangle created by walking along `+z/-x..+x` cube coordinates.
@@ -232,14 +276,14 @@ pub fn rectangle_zx_even(dz: i32, dx: i32) -> Shape<impl Iterator<Item=Cube>> {
 /// 2 |       |
 /// 3 >_______>
 /// ```
-pub fn rectangle_zx_odd(dz: i32, dx: i32) -> Shape<impl Iterator<Item=Cube>> {
+pub fn rectangle_zx_odd(dz: i32, dx: i32) -> Shape<impl ExactSizeIterator<Item=Cube>> {
     let data = (0 .. dz).flat_map(move |z| {
         let z_offset = z / 2;
         (-z_offset .. dx - z_offset).map(move |x| {
             Cube::new_xz(x, z)
         })
     });
-    Shape { data, total: (dz * dx) as usize }
+    exact(data, area(dz, dx))
 }
 
 
@@ -249,14 +293,14 @@ pub fn rectangle_zx_odd(dz: i32, dx: i32) -> Shape<impl Iterator<Item=Cube>> {
 /// with the origin at the bottom left corner.
 ///
 /// Pointy-Top: Flat-top rotated 30 degrees counterclockwise.
-pub fn rectangle_xy(dx: i32, dy: i32) -> Shape<impl Iterator<Item=Cube>> {
+pub fn rectangle_xy(dx: i32, dy: i32) -> Shape<impl ExactSizeIterator<Item=Cube>> {
     let data = (0 .. dx).flat_map(move |x| {
         let x_offset = x / 2;
         (-x_offset .. dy - x_offset).map(move |y| {
             Cube::new_xy(x,y)
         })
     });
-    Shape { data, total: (dx * dy) as usize }
+    exact(data, area(dx, dy))
 }
 
 /// A rectangle created by walking along `+z/-y..+y` cube coordinates.
@@ -265,14 +309,14 @@ pub fn rectangle_xy(dx: i32, dy: i32) -> Shape<impl Iterator<Item=Cube>> {
 /// top-right corner.
 ///
 /// Flat-Top: Pointy-top rotated 30 degrees clockwise.
-pub fn rectangle_zy(dz: i32, dy: i32) -> Shape<impl Iterator<Item=Cube>> {
+pub fn rectangle_zy(dz: i32, dy: i32) -> Shape<impl ExactSizeIterator<Item=Cube>> {
     let data = (0 .. dz).flat_map(move |z| {
         let z_offset = z / 2;
         (-z_offset .. dy - z_offset).map(move |y| {
             Cube::new_yz(y, z)
         })
     });
-    Shape { data, total: (dy * dz) as usize }
+    exact(data, area(dy, dz))
 }
 
 /// A rectangle created by walking along `+y/-x..+x` cube coordinates.
@@ -282,14 +326,14 @@ pub fn rectangle_zy(dz: i32, dy: i32) -> Shape<impl Iterator<Item=Cube>> {
 ///
 /// Pointy-Top: A flat-top axis-aligned grid with the origin at the
 /// bottom-right corner, rotated 30 degrees clockwise.
-pub fn rectangle_yx(dy: i32, dx: i32) -> Shape<impl Iterator<Item=Cube>> {
+pub fn rectangle_yx(dy: i32, dx: i32) -> Shape<impl ExactSizeIterator<Item=Cube>> {
     let data = (0 .. dy).flat_map(move |y| {
         let y_offset = y / 2;
         (-y_offset .. dx - y_offset).map(move |x| {
             Cube::new_xy(x,y)
         })
     });
-    Shape { data, total: (dx * dy) as usize }
+    exact(data, area(dx, dy))
 }
 
 /// A rectangle created by walking along `+y/-z..+z` cube coordinates.
@@ -299,14 +343,14 @@ pub fn rectangle_yx(dy: i32, dx: i32) -> Shape<impl Iterator<Item=Cube>> {
 ///
 /// Pointy-Top: A flat-top axis-aligned rectangle with the origin
 /// in the top-right corner, rotated 30 degrees clockwise.
-pub fn rectangle_yz(dy: i32, dz: i32) -> Shape<impl Iterator<Item=Cube>> {
+pub fn rectangle_yz(dy: i32, dz: i32) -> Shape<impl ExactSizeIterator<Item=Cube>> {
     let data = (0 .. dy).flat_map(move |y| {
         let y_offset = y / 2;
         (-y_offset .. dz - y_offset).map(move |z| {
             Cube::new_yz(y, z)
         })
     });
-    Shape { data, total: (dy * dz) as usize }
+    exact(data, area(dy, dz))
 }
 
 //////////////////////////////////////////////////////////////////////////////
@@ -340,11 +384,11 @@ pub fn rectangle_yz(dy: i32, dz: i32) -> Shape<impl Iterator<Item=Cube>> {
 ///   \  /
 ///    \/
 /// ```
-pub fn triangle_xy(dx: i32) -> Shape<impl Iterator<Item=Cube>> {
+pub fn triangle_xy(dx: i32) -> Shape<impl ExactSizeIterator<Item=Cube>> {
     let data = (0 .. dx).flat_map(move |x| {
         (x .. dx).map(move |y| Cube::new_xy(x, -y))
     });
-    Shape { data, total: (dx * (dx + 1) / 2) as usize }
+    exact(data, triangle_area(dx))
 }
 
 /// A triangle created by walking along `-y/+x` cube coordinates,
@@ -372,11 +416,11 @@ pub fn triangle_xy(dx: i32) -> Shape<impl Iterator<Item=Cube>> {
 ///  /    \
 /// .______\
 /// ```
-pub fn triangle_yx(dy: i32) -> Shape<impl Iterator<Item=Cube>> {
+pub fn triangle_yx(dy: i32) -> Shape<impl ExactSizeIterator<Item=Cube>> {
     let data = (0 .. dy).flat_map(move |y| {
         (y .. dy).map(move |x| Cube::new_xy(x, -y))
     });
-    Shape { data, total: (dy * (dy + 1) / 2) as usize }
+    exact(data, triangle_area(dy))
 }
 
 #[cfg(test)]
@@ -406,5 +450,47 @@ mod tests {
             Shape { data, total }
         }
     }
+
+    fn check<I: ExactSizeIterator<Item=Cube>>(shape: Shape<I>) -> bool {
+        let total = shape.total;
+        let mut data = shape.data;
+        let mut ok = data.len() == total;
+        let mut count = 0;
+        while data.next().is_some() {
+            count += 1;
+            ok &= data.len() == total - count;
+        }
+        ok && count == total
+    }
+
+    #[test]
+    fn prop_shape_len() {
+        fn prop(n1: i8, n2: i8) -> bool {
+            let (n1, n2) = (n1 as i32 % 20, n2 as i32 % 20);
+            check(hexagon(n1.unsigned_abs() as u16 + 1)) &&
+            check(parallelogram_xy(n1, n2)) &&
+            check(parallelogram_xz(n1, n2)) &&
+            check(parallelogram_yz(n1, n2)) &&
+            check(rectangle_xz_even(n1, n2)) &&
+            check(rectangle_xz_odd(n1, n2)) &&
+            check(rectangle_zx_even(n1, n2)) &&
+            check(rectangle_zx_odd(n1, n2)) &&
+            check(rectangle_xy(n1, n2)) &&
+            check(rectangle_zy(n1, n2)) &&
+            check(rectangle_yx(n1, n2)) &&
+            check(rectangle_yz(n1, n2)) &&
+            check(triangle_xy(n1)) &&
+            check(triangle_yx(n1))
+        }
+        quickcheck(prop as fn(_,_) -> _);
+    }
+
+    #[test]
+    fn test_negative_sizes() {
+        assert_eq!(rectangle_xz_odd(-3, 4).total, 0);
+        assert_eq!(rectangle_xz_odd(3, -4).total, 0);
+        assert_eq!(parallelogram_xy(-3, -4).total, 0);
+        assert_eq!(triangle_xy(-3).total, 0);
+    }
 }
 
@@ -0,0 +1,126 @@
+
+//! Recording and replaying of input commands.
+
+use std::time::Duration;
+
+/// An input command recorded at a certain point in time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry<I> {
+    /// The time at which the input was recorded, relative
+    /// to the start of the recording.
+    pub time: Duration,
+    pub input: I,
+}
+
+/// A chronological log of input commands, e.g. for replays
+/// and deterministic tests of game logic.
+#[derive(Clone, Debug)]
+pub struct InputLog<I> {
+    entries: Vec<Entry<I>>,
+}
+
+impl<I> Default for InputLog<I> {
+    fn default() -> Self {
+        InputLog::new()
+    }
+}
+
+impl<I> InputLog<I> {
+    /// Create a new, empty input log.
+    pub fn new() -> InputLog<I> {
+        InputLog { entries: Vec::new() }
+    }
+
+    /// Record an input at the given time, which must not be earlier
+    /// than the time of the last recorded input.
+    pub fn record(&mut self, time: Duration, input: I) {
+        if let Some(last) = self.entries.last() {
+            assert!(last.time <= time, "time < last recorded time");
+        }
+        self.entries.push(Entry { time, input })
+    }
+
+    /// The number of recorded inputs.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over the recorded inputs in chronological order.
+    pub fn iter(&self) -> impl Iterator<Item=&Entry<I>> {
+        self.entries.iter()
+    }
+
+    /// Replay all recorded inputs in chronological order by applying
+    /// them with the given function, stopping at the first error.
+    pub fn replay<F, E>(&self, apply: F) -> Result<(), E>
+    where
+        F: FnMut(&I) -> Result<(), E>
+    {
+        self.replay_until(Duration::from_secs(u64::MAX), apply)
+    }
+
+    /// Replay the recorded inputs up to and including the given time
+    /// in chronological order by applying them with the given function,
+    /// stopping at the first error.
+    pub fn replay_until<F, E>(&self, time: Duration, mut apply: F) -> Result<(), E>
+    where
+        F: FnMut(&I) -> Result<(), E>
+    {
+        for e in self.entries.iter().take_while(|e| e.time <= time) {
+            apply(&e.input)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::*;
+
+    #[derive(Clone, Debug)]
+    enum Input {
+        Push(i32),
+        Pop,
+    }
+
+    fn apply(world: &mut Vec<i32>, input: &Input) -> Result<(), ()> {
+        match input {
+            Input::Push(x) => world.push(*x),
+            Input::Pop => { world.pop(); }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn prop_record_replay() {
+        fn prop(inputs: Vec<Option<i32>>) -> bool {
+            let mut world = Vec::new();
+            let mut log = InputLog::new();
+            for (i, x) in inputs.iter().enumerate() {
+                let input = x.map_or(Input::Pop, Input::Push);
+                apply(&mut world, &input).unwrap();
+                log.record(Duration::from_millis(i as u64), input);
+            }
+            let mut replayed = Vec::new();
+            log.replay(|i| apply(&mut replayed, i)).unwrap();
+            log.len() == inputs.len() && replayed == world
+        }
+        quickcheck(prop as fn(_) -> _);
+    }
+
+    #[test]
+    fn test_replay_until() {
+        let mut log = InputLog::new();
+        log.record(Duration::from_secs(1), Input::Push(1));
+        log.record(Duration::from_secs(2), Input::Push(2));
+        log.record(Duration::from_secs(3), Input::Pop);
+        let mut world = Vec::new();
+        log.replay_until(Duration::from_secs(2), |i| apply(&mut world, i)).unwrap();
+        assert_eq!(world, vec![1, 2]);
+    }
+}
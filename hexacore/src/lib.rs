@@ -4,8 +4,12 @@
 //! A toolkit for the construction and use of hexagonal maps,
 //! e.g. in the context of game programming.
 
+pub mod event;
 pub mod geo;
 pub mod grid;
+pub mod input;
 pub mod ui;
 pub mod search;
+pub mod turn;
+pub mod util;
 
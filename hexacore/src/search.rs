@@ -3,15 +3,63 @@
 
 pub mod astar;
 pub mod bfs;
+pub mod combinator;
+pub mod fcost;
+pub mod heuristic;
+#[cfg(test)]
+mod regression;
 
+pub use combinator::{ layered, blocked_by, within_grid };
+
+use crate::geo::Z6;
+use crate::grid::Grid;
 use crate::grid::coords::{ self, Coords };
 
+use smallvec::SmallVec;
+
 use std::borrow::Borrow;
 use std::collections::{ HashMap, VecDeque };
 use std::ops::Deref;
 
+/// The cost of moving between adjacent coordinates in a search.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Cost {
+    /// The target coordinates can be entered at the given cost.
+    Passable(usize),
+    /// The target coordinates can never be entered, regardless of
+    /// the budget of the search.
+    Impassable,
+}
+
+impl Cost {
+    /// The cost of a passable move, if any.
+    pub fn passable(self) -> Option<usize> {
+        match self {
+            Cost::Passable(c) => Some(c),
+            Cost::Impassable => None,
+        }
+    }
+}
+
+impl From<Option<usize>> for Cost {
+    fn from(cost: Option<usize>) -> Cost {
+        cost.map_or(Cost::Impassable, Cost::Passable)
+    }
+}
+
 /// The context of a search defines the cost and bounds of the search space.
+///
+/// There are two distinct reasons for coordinates not being reached by
+/// a search:
+///
+///   * The coordinates are impassable, i.e. the cost of entering them is
+///     [`Cost::Impassable`] (`None` for [`Context::cost`]). No search ever
+///     enters them, e.g. because they are blocked.
+///   * The coordinates are too expensive, i.e. the total cost of every path
+///     to the coordinates exceeds the `max_cost` of the context (or the
+///     budget of the search). Searches with a larger budget may enter them.
 pub trait Context<C: Coords> {
+    /// The maximum total cost of the paths of a search, i.e. its budget.
     fn max_cost(&self) -> usize {
         std::usize::MAX
     }
@@ -21,10 +69,166 @@ pub trait Context<C: Coords> {
     fn exit(&mut self, _next: C) -> bool {
         false
     }
+    /// An estimate of the cost of reaching the given goal coordinates,
+    /// which must not exceed the actual cost for the A* search to find
+    /// optimal paths (see [`heuristic`](heuristic/index.html)).
+    ///
+    /// The A* search also relies on the heuristic being admissible to stop
+    /// early when the goal is estimated to exceed the `max_cost` of the
+    /// context (or the budget of the search). With a heuristic that
+    /// overestimates the cost, such as [`heuristic::weighted`] with a
+    /// weight above the minimum cost of a step, a goal that is within the
+    /// budget may thus not be found.
+    ///
+    /// By default, this is the distance between the coordinates, or half
+    /// the distance (rounded up) if [`Context::include_diagonals`], since
+    /// a diagonal move bridges a distance of 2.
     fn heuristic(&mut self, from: C, to: C) -> usize {
-        coords::distance(from, to)
+        let d = heuristic::hex_distance(from, to);
+        if self.include_diagonals() { d.div_ceil(2) } else { d }
+    }
+    /// The coordinates that are adjacent to the given coordinates in the
+    /// search space, which are by default the neighbouring coordinates,
+    /// as well as the diagonal neighbours if [`Context::include_diagonals`].
+    /// Overriding the adjacency allows e.g. for portals or one-way
+    /// connections.
+    fn neighbours(&self, c: C) -> SmallVec<[C; 8]> {
+        if self.include_diagonals() {
+            coords::neighbours(c).chain(coords::diagonal_neighbours(c)).collect()
+        } else {
+            coords::neighbours(c).collect()
+        }
     }
+    /// Whether moves along the diagonal axes, i.e. to the nearest coordinates
+    /// across a corner (see [`coords::diagonal_neighbours`]) are permitted,
+    /// at the cost given by [`Context::cost`], like any other move.
+    ///
+    /// Note that a diagonal move bridges a (beeline) distance of 2, so with
+    /// diagonals the number of moves of a path may be smaller than the
+    /// distance between its start and end, whereas the `max_distance` of the
+    /// context continues to refer to the distance. Custom heuristics must
+    /// take this into account to remain admissible.
+    fn include_diagonals(&self) -> bool {
+        false
+    }
+    /// A penalty for every change of direction along a path, which lets a
+    /// cost-aware search prefer straighter paths among paths of otherwise
+    /// equal cost. The penalties are only compared between paths of equal
+    /// cost, i.e. they neither count towards the cost of a path, nor
+    /// against the `max_cost` of the context.
+    ///
+    /// Only steps to neighbours and diagonal neighbours have a direction,
+    /// so moves between other coordinates, e.g. through a portal (see
+    /// [`Context::neighbours`]), never count as a change of direction.
+    /// Only the path to the goal of a search is guaranteed to have the
+    /// fewest changes of direction, not the paths to the other coordinates
+    /// of the search tree.
+    fn turn_penalty(&self) -> usize {
+        0
+    }
+    /// The maximum number of coordinates expanded by a search, bounding
+    /// the time spent on a search regardless of the costs, e.g. to
+    /// guarantee a bounded frame time on large, open maps. A search
+    /// that reaches this limit stops early and its result may thus be
+    /// incomplete, i.e. lack reachable coordinates or paths.
+    fn max_expansions(&self) -> usize {
+        usize::MAX
+    }
+    /// The cost of moving from the given coordinates to the given adjacent
+    /// coordinates, or `None` if the latter are impassable.
     fn cost(&mut self, from: C, to: C) -> Option<usize>;
+    /// Like [`Context::cost`], but making the distinction between
+    /// passable and impassable coordinates explicit. This is the cost
+    /// function used by the searches and defaults to [`Context::cost`].
+    fn cost_ex(&mut self, from: C, to: C) -> Cost {
+        Cost::from(self.cost(from, to))
+    }
+}
+
+/// A grid whose tiles are marked as passable or impassable is a search
+/// context with a uniform cost for the passable tiles.
+impl<C: Coords> Context<C> for Grid<C, bool> {
+    fn neighbours(&self, c: C) -> SmallVec<[C; 8]> {
+        self.cached_neighbours(c).iter().copied().collect()
+    }
+    fn cost(&mut self, _from: C, to: C) -> Option<usize> {
+        Some(1).filter(|_| self.payload(to) == Some(&true))
+    }
+}
+
+/// An upper bound for the number of coordinates reachable from the given
+/// start coordinates (including the start) within the given budget. The
+/// estimate is the number of coordinates within the range given by the
+/// budget and the `max_distance` of the context, where every step bridges
+/// a distance of 1, or of 2 if [`Context::include_diagonals`], and does
+/// not involve a search, making it suitable e.g. for quick heuristics of
+/// an AI. Use [`reachable_count`] where the exact number is needed.
+///
+/// The estimate is only an upper bound if every step costs at least 1 and
+/// the context does not override [`Context::neighbours`], e.g. for portals,
+/// since otherwise more distant coordinates may be reachable.
+pub fn reachable_count_estimate<C: Coords>(
+    _start: C,
+    budget: usize,
+    ctx: &impl Context<C>
+) -> usize {
+    let steps = if ctx.include_diagonals() { budget.saturating_mul(2) } else { budget };
+    let r = steps.min(ctx.max_distance()).min(u16::MAX as usize);
+    coords::num_in_range(r as u16)
+}
+
+/// The exact number of coordinates reachable from the given start
+/// coordinates (including the start) within the given budget, as
+/// determined by a cost-aware search, see [`astar::tree_with_budget`].
+pub fn reachable_count<C: Coords>(
+    start: C,
+    budget: usize,
+    ctx: &mut impl Context<C>
+) -> usize {
+    astar::tree_with_budget(start, None, budget, ctx).iter().count()
+}
+
+/// Check whether the given goal coordinates are reachable from the given
+/// start coordinates at all, ignoring the costs of the moves except for
+/// impassable coordinates and the `max_cost` of the context for a single
+/// move, as for a [`bfs`] search, which stops as soon as the goal is found.
+/// This is cheaper than a cost-aware search, e.g. for validating a move
+/// before searching for the cheapest path.
+///
+/// If the goal is unreachable, the search space is exhaustively searched
+/// and must thus be bounded by the context, e.g. via `max_distance`.
+pub fn reachable<C: Coords>(
+    start: C,
+    goal: C,
+    ctx: &mut impl Context<C>
+) -> bool {
+    start == goal || bfs::tree(start, Some(goal), ctx).path(goal).is_some()
+}
+
+/// The costs of the cheapest paths between all pairs of coordinates of the
+/// given region that are reachable from each other, including the pairs of
+/// the same coordinates at cost 0, as determined by a cost-aware search
+/// (see [`astar::tree`]) from every coordinates of the region, e.g. for a
+/// tactical AI evaluating a small area.
+///
+/// Since every search covers the whole search space within the `max_cost`
+/// and `max_distance` of the context, and not just the region, this is
+/// intended for small regions and the search space must be bounded by the
+/// context. The paths may leave the region.
+pub fn all_pairs<C: Coords>(
+    region: &[C],
+    ctx: &mut impl Context<C>
+) -> HashMap<(C, C), usize> {
+    let mut costs = HashMap::new();
+    for a in region {
+        let tree = astar::tree(*a, None, ctx);
+        for b in region {
+            if let Some(cost) = tree.cost(*b) {
+                costs.insert((*a, *b), cost);
+            }
+        }
+    }
+    costs
 }
 
 /// A node in a path of a search tree.
@@ -72,6 +276,48 @@ impl<C> Path<C> {
     pub fn to_vec(self) -> Vec<Node<C>> {
         Vec::from(self.0)
     }
+
+    /// The total cost of the path, i.e. the cost of the last node.
+    pub fn total_cost(&self) -> usize {
+        self.0.back().map_or(0, |n| n.cost)
+    }
+
+    /// Truncate the path to the longest prefix whose total cost
+    /// does not exceed the given budget.
+    pub fn truncate_to_cost(&mut self, budget: usize) {
+        let len = self.0.iter().take_while(|n| n.cost <= budget).count();
+        self.0.truncate(len)
+    }
+}
+
+impl<C: Copy> Path<C> {
+    /// Iterate over the coordinates of the nodes of the path.
+    pub fn coords(&self) -> impl DoubleEndedIterator<Item=C> + ExactSizeIterator + '_ {
+        self.0.iter().map(|n| n.coords)
+    }
+
+    /// The coordinates of the first node of the path, i.e. the start.
+    pub fn first_coords(&self) -> Option<C> {
+        self.0.front().map(|n| n.coords)
+    }
+
+    /// The coordinates of the last node of the path, i.e. the goal.
+    pub fn last_coords(&self) -> Option<C> {
+        self.0.back().map(|n| n.coords)
+    }
+}
+
+impl<C: Coords> Path<C> {
+    /// The directions of the steps between consecutive nodes of the path,
+    /// e.g. for a compact encoding of movements, or `None` if any step
+    /// is not between adjacent coordinates. Applying the directions
+    /// (see [`CubeVec::direction`](crate::grid::CubeVec::direction))
+    /// to the start coordinates yields the coordinates of the path.
+    pub fn directions(&self) -> Option<Vec<Z6>> {
+        self.coords().zip(self.coords().skip(1))
+            .map(|(a, b)| (b.to_cube() - a.to_cube()).direction_index())
+            .collect()
+    }
 }
 
 /// A `Tree` is constructed as the result of a search on a grid.
@@ -100,6 +346,15 @@ impl<C: Coords> Tree<C> {
         self.costs.iter()
     }
 
+    /// Like [`Tree::iter`], but yielding the coordinates in a stable order,
+    /// i.e. ordered by their `x` and then `y` cube coordinates, e.g. for
+    /// drawing overlapping or translucent overlays consistently.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = (C, usize)> {
+        let mut costs = self.costs.iter().map(|(c, cost)| (*c, *cost)).collect::<Vec<_>>();
+        costs.sort_by_key(|(c, _)| { let c = c.to_cube(); (c.x(), c.y()) });
+        costs.into_iter()
+    }
+
     /// Trace a path from the given goal back to the root of the tree. The path
     /// is returned in the natural (i.e. reverse) order from start to goal.
     pub fn path(&self, goal: C) -> Option<Path<C>> {
@@ -118,5 +373,251 @@ impl<C: Coords> Tree<C> {
         }
         Some(Path(path))
     }
+
+    /// Like [`Tree::path`], but yielding every node of the path with
+    /// the incremental cost of the step to the node, rather than the
+    /// cumulative cost, e.g. for annotating where costs arise along
+    /// a path. The root node has an incremental cost of `0`.
+    pub fn path_breakdown(&self, goal: C) -> Option<Vec<(C, usize)>> {
+        let path = self.path(goal)?;
+        let mut prev = 0;
+        Some(path.iter().map(|n| {
+            let cost = n.cost - prev;
+            prev = n.cost;
+            (n.coords, cost)
+        }).collect())
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::coords::{ Cube, CubeVec };
+    use quickcheck::*;
+
+    fn path(costs: &[usize]) -> Path<Cube> {
+        Path(costs.iter().enumerate()
+            .map(|(i, c)| Node::new(Cube::new_xy(i as i32, 0), *c))
+            .collect())
+    }
+
+    #[test]
+    fn test_path_total_cost() {
+        assert_eq!(Path::<Cube>::empty().total_cost(), 0);
+        assert_eq!(path(&[0, 1, 3, 4]).total_cost(), 4);
+    }
+
+    #[test]
+    fn test_path_truncate_to_cost() {
+        let mut p = path(&[0, 1, 3, 4]);
+        p.truncate_to_cost(5);
+        assert_eq!(p.len(), 4);
+        p.truncate_to_cost(3);
+        assert_eq!(p.len(), 3);
+        assert_eq!(p.total_cost(), 3);
+        p.truncate_to_cost(2);
+        assert_eq!(p.len(), 2);
+        assert_eq!(p.total_cost(), 1);
+        p.truncate_to_cost(0);
+        assert_eq!(p.len(), 1);
+    }
+
+    struct Obstacles {
+        cost: usize,
+        blocked: Vec<Cube>,
+    }
+
+    impl Context<Cube> for Obstacles {
+        fn cost(&mut self, _from: Cube, to: Cube) -> Option<usize> {
+            Some(self.cost).filter(|_| !self.blocked.contains(&to))
+        }
+    }
+
+    #[test]
+    fn prop_reachable_count() {
+        fn prop(start: Cube, budget: u8, cost: u8, blocked: Vec<Cube>) -> bool {
+            let budget = budget as usize % 10;
+            let blocked = blocked.iter()
+                .map(|c| Cube::new_xz(start.x() + c.x() % 5, start.z() + c.z() % 5))
+                .collect();
+            let ctx = &mut Obstacles { cost: cost as usize % 3 + 1, blocked };
+            let count = reachable_count(start, budget, ctx);
+            let estimate = reachable_count_estimate(start, budget, ctx);
+            let unobstructed = reachable_count(start, budget, &mut Obstacles { cost: 1, blocked: vec![] });
+            1 <= count && count <= estimate && unobstructed == estimate
+        }
+        quickcheck(prop as fn(_,_,_,_) -> _);
+    }
+
+    struct Diagonals {
+        cost: usize,
+    }
+
+    impl Context<Cube> for Diagonals {
+        fn include_diagonals(&self) -> bool {
+            true
+        }
+        fn cost(&mut self, _from: Cube, _to: Cube) -> Option<usize> {
+            Some(self.cost)
+        }
+    }
+
+    #[test]
+    fn test_reachable_count_estimate_diagonals() {
+        let start = Cube::origin();
+        for cost in 1 ..= 2 {
+            let ctx = &mut Diagonals { cost };
+            for budget in 0 .. 6 {
+                let count = reachable_count(start, budget, ctx);
+                let estimate = reachable_count_estimate(start, budget, ctx);
+                assert!(count <= estimate, "{} > {} for budget {}", count, estimate, budget);
+            }
+        }
+        // With diagonals, a single step reaches a distance of 2.
+        let ctx = &mut Diagonals { cost: 1 };
+        assert_eq!(reachable_count(start, 1, ctx), 13);
+        assert_eq!(reachable_count_estimate(start, 1, ctx), coords::num_in_range(2));
+    }
+
+    #[test]
+    fn test_path_coords() {
+        let p = path(&[0, 1, 3, 4]);
+        let coords = p.iter().map(|n| n.coords).collect::<Vec<_>>();
+        assert!(p.coords().eq(coords.iter().copied()));
+        assert_eq!(p.first_coords(), coords.first().copied());
+        assert_eq!(p.last_coords(), coords.last().copied());
+        assert_eq!(Path::<Cube>::empty().first_coords(), None);
+        assert_eq!(Path::<Cube>::empty().last_coords(), None);
+    }
+
+    #[test]
+    fn prop_path_directions() {
+        fn prop(start: Cube, goal: (i8, i8)) -> bool {
+            let goal = Cube::new_xz(start.x() + goal.0 as i32 % 8, start.z() + goal.1 as i32 % 8);
+            let ctx = &mut Obstacles { cost: 1, blocked: vec![] };
+            let p = astar::path(start, goal, ctx).unwrap();
+            let dirs = p.directions().unwrap();
+            let replay = dirs.iter().scan(start, |c, z| {
+                *c = *c + CubeVec::directions().nth(*z as usize).unwrap();
+                Some(*c)
+            });
+            dirs.len() + 1 == p.len() && p.coords().skip(1).eq(replay)
+        }
+        quickcheck(prop as fn(_,_) -> _);
+    }
+
+    #[test]
+    fn test_path_directions_not_adjacent() {
+        let p = Path(vec![Node::new(Cube::origin(), 0), Node::new(Cube::new_xy(2, 0), 1)].into());
+        assert_eq!(p.directions(), None);
+        assert_eq!(Path::<Cube>::empty().directions(), Some(vec![]));
+    }
+
+    struct Rough;
+
+    impl Context<Cube> for Rough {
+        fn cost(&mut self, _from: Cube, to: Cube) -> Option<usize> {
+            Some(1 + to.x().unsigned_abs() as usize % 3)
+        }
+    }
+
+    #[test]
+    fn prop_path_breakdown() {
+        fn prop(goal: (i8, i8)) -> bool {
+            let start = Cube::origin();
+            let goal = Cube::new_xz(goal.0 as i32 % 6, goal.1 as i32 % 6);
+            let tree = astar::tree(start, Some(goal), &mut Rough);
+            let path = tree.path(goal).unwrap();
+            let breakdown = tree.path_breakdown(goal).unwrap();
+            breakdown.iter().map(|(_, cost)| cost).sum::<usize>() == path.total_cost() &&
+            breakdown.iter().map(|(c, _)| *c).eq(path.coords()) &&
+            breakdown.iter().skip(1).all(|(c, cost)| *cost == Rough.cost(start, *c).unwrap()) &&
+            breakdown[0] == (start, 0)
+        }
+        quickcheck(prop as fn(_) -> _);
+    }
+
+    /// A context whose costs do not depend on the direction of a move.
+    struct Symmetric;
+
+    impl Context<Cube> for Symmetric {
+        fn max_distance(&self) -> usize {
+            6
+        }
+        fn cost(&mut self, from: Cube, to: Cube) -> Option<usize> {
+            let x = from.x().abs() + to.x().abs();
+            Some(1 + x as usize % 3).filter(|_| to.y() != 2 && from.y() != 2)
+        }
+    }
+
+    #[test]
+    fn test_all_pairs_symmetric() {
+        let region = coords::range(Cube::origin(), 2).collect::<Vec<_>>();
+        let costs = all_pairs(&region, &mut Symmetric);
+        for a in &region {
+            assert_eq!(costs.get(&(*a, *a)), Some(&0));
+            for b in &region {
+                assert_eq!(costs.get(&(*a, *b)), costs.get(&(*b, *a)));
+            }
+        }
+        // The tiles with y = 2 are impassable.
+        let blocked = region.iter().filter(|c| c.y() == 2).count();
+        assert!(blocked > 0);
+        assert_eq!(costs.len(), (region.len() - blocked).pow(2) + blocked);
+    }
+
+    /// A walled-off area of radius 1 around the origin.
+    struct Walled;
+
+    impl Context<Cube> for Walled {
+        fn max_distance(&self) -> usize {
+            8
+        }
+        fn cost(&mut self, _from: Cube, to: Cube) -> Option<usize> {
+            Some(1).filter(|_| coords::distance(Cube::origin(), to) != 2)
+        }
+    }
+
+    #[test]
+    fn test_reachable() {
+        let inside = Cube::new_xz(1, 0);
+        let outside = Cube::new_xz(3, -1);
+        assert!(reachable(Cube::origin(), inside, &mut Walled));
+        assert!(reachable(outside, Cube::new_xz(-3, 0), &mut Walled));
+        assert!(reachable(inside, inside, &mut Walled));
+        assert!(!reachable(Cube::origin(), outside, &mut Walled));
+        assert!(!reachable(outside, inside, &mut Walled));
+        // The walls themselves are impassable.
+        assert!(!reachable(inside, Cube::new_xz(2, 0), &mut Walled));
+    }
+
+    #[test]
+    fn test_tree_iter_sorted() {
+        let tree = astar::tree(Cube::new_xz(3, -1), None, &mut Walled);
+        let sorted = tree.iter_sorted().collect::<Vec<_>>();
+        assert_eq!(sorted.len(), tree.iter().count());
+        assert!(sorted.windows(2).all(|w| (w[0].0.x(), w[0].0.y()) < (w[1].0.x(), w[1].0.y())));
+        assert!(sorted.iter().all(|(c, cost)| tree.cost(*c) == Some(*cost)));
+        assert_eq!(tree.iter_sorted().collect::<Vec<_>>(), sorted);
+    }
+
+    #[test]
+    fn test_grid_context() {
+        use crate::geo::{ Orientation, Schema, SideLength };
+        use crate::grid::shape;
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let mut grid: Grid<Cube, bool> = Grid::new(schema, shape::hexagon(3));
+        let cs = grid.iter().map(|(c, _)| *c).collect::<Vec<_>>();
+        for c in cs {
+            grid.set_payload(c, true);
+        }
+        let wall = Cube::new_xz(1, 0);
+        grid.set_payload(wall, false);
+        let goal = Cube::new_xz(2, 0);
+        let p = astar::path(Cube::origin(), goal, &mut grid).unwrap();
+        assert_eq!(p.total_cost(), 3);
+        assert!(p.coords().all(|c| c != wall));
+        // Coordinates outside of the grid are neither adjacent nor passable.
+        assert!(astar::path(Cube::origin(), Cube::new_xz(3, 0), &mut grid).is_none());
+    }
+}
@@ -4,19 +4,31 @@ use std::collections::BinaryHeap;
 use std::collections::HashMap;
 
 use crate::grid::Coords;
-use crate::grid::coords::{ self, Cube };
+use crate::grid::coords::{ self, Cube, CubeVec };
 
-use super::{ Context, Tree, Path };
+use super::{ Context, Cost, Tree, Path };
+
+/// A state of the search, i.e. coordinates together with the direction
+/// in which they were entered, if it matters for the turn penalty (see
+/// [`Context::turn_penalty`]). Without a turn penalty, the direction is
+/// always `None` and the states are just the coordinates.
+type State = (Cube, Option<CubeVec>);
 
 /// A node in the "open" list of the A* algorithm to prioritise the search.
 struct Open {
-    coords: Cube,
-    priority: usize
+    state: State,
+    /// The cost of reaching the state at the time it was (re-)opened,
+    /// which is stale if it has been reached at a lower cost since.
+    cost: usize,
+    /// The sum of the turn penalties at the time the state was
+    /// (re-)opened, which breaks ties between equal costs.
+    penalty: usize,
+    priority: usize,
 }
 
 impl PartialEq for Open {
     fn eq(&self, other: &Open) -> bool {
-        self.priority == other.priority
+        self.priority == other.priority && self.penalty == other.penalty
     }
 }
 
@@ -33,6 +45,19 @@ impl Ord for Open {
         // Lower priorities (= estimated total costs)
         // are considered "greater" for the binary heap.
         other.priority.cmp(&self.priority)
+            .then(other.penalty.cmp(&self.penalty))
+    }
+}
+
+/// The direction of a step between the given coordinates that is
+/// subject to the turn penalty, i.e. of a step to a neighbour or a
+/// diagonal neighbour, as opposed to e.g. a portal.
+fn step_direction(from: Cube, to: Cube) -> Option<CubeVec> {
+    let v = to - from;
+    if v.is_unit() || CubeVec::diagonals().any(|d| d == v) {
+        Some(v)
+    } else {
+        None
     }
 }
 
@@ -45,47 +70,110 @@ impl Ord for Open {
 ///   * Goal coordinates are given and found.
 ///   * The `exit` function signals termination.
 ///   * The grid has been exhaustively searched.
+///   * Goal coordinates are given and every remaining path to the goal
+///     would exceed the `max_cost` of the context, as estimated by the
+///     heuristic, in which case the search tree is partial. This requires
+///     the heuristic to be admissible (see [`Context::heuristic`]).
+///   * The `max_expansions` of the context are reached, in which case
+///     the search tree is partial, i.e. it may lack reachable coordinates
+///     and the costs of the coordinates it contains may not be optimal.
 pub fn tree<C: Coords>(
     start: C,
     goal: Option<C>,
     ctx: &mut impl Context<C>
+) -> Tree<C> {
+    let budget = ctx.max_cost();
+    tree_with_budget(start, goal, budget, ctx)
+}
+
+/// Like [`tree`], but with a maximum cost (i.e. budget) for the search
+/// that overrides the `max_cost` of the context. This allows reusing
+/// the same context for searches with different budgets, e.g. for
+/// entities with different movement ranges.
+pub fn tree_with_budget<C: Coords>(
+    start: C,
+    goal: Option<C>,
+    budget: usize,
+    ctx: &mut impl Context<C>
 ) -> Tree<C> {
     let root         = start.into();
-    let max_cost     = ctx.max_cost();
+    let max_cost     = budget;
     let max_distance = ctx.max_distance();
+    let turn_penalty = ctx.turn_penalty();
+    let mut expansions = ctx.max_expansions();
     let mut parents  = HashMap::new();
     let mut costs    = HashMap::new();
+    let mut states   = HashMap::new();
+    let mut state_parents = HashMap::new();
     let mut open     = BinaryHeap::new();
-    open.push(Open { coords: root, priority: 0 });
+    let mut reached  = None;
+    open.push(Open { state: (root, None), cost: 0, penalty: 0, priority: 0 });
     costs.insert(start, 0);
+    states.insert((root, None), (0, 0));
     while let Some(parent) = open.pop() {
-        let pc = C::from(parent.coords);
-        if ctx.exit(pc) || goal.map_or(false, |g| g == pc) {
+        let (pcube, pdir) = parent.state;
+        let pc = C::from(pcube);
+        if matches!(states.get(&parent.state), Some(c) if *c < (parent.cost, parent.penalty)) {
+            // The state has been expanded at a lower cost already.
+            continue
+        }
+        if goal.is_some() && parent.priority > max_cost {
+            // The open list is ordered by priority and all priorities
+            // are lower bounds of the costs of reaching the goal, which
+            // is thus out of reach.
+            break
+        }
+        let at_goal = goal == Some(pc);
+        if ctx.exit(pc) || at_goal {
+            reached = Some(parent.state).filter(|_| at_goal);
             break
         }
-        for child in coords::neighbours(parent.coords) {
-            let cc = C::from(child);
+        if expansions == 0 {
+            break
+        }
+        expansions -= 1;
+        for cc in ctx.neighbours(pc) {
+            let child = cc.into();
             if coords::distance(child, root) > max_distance {
                 continue
             }
-            let new_cost = if let Some(cost) = ctx.cost(pc, cc) {
-                *costs.get(&pc).unwrap_or(&0) + cost
+            let dir = if turn_penalty > 0 { step_direction(pcube, child) } else { None };
+            let turn = match (pdir, dir) {
+                (Some(a), Some(b)) if a != b => turn_penalty,
+                _ => 0
+            };
+            let new_cost = if let Cost::Passable(cost) = ctx.cost_ex(pc, cc) {
+                parent.cost + cost
             } else {
                 continue
             };
             if new_cost > max_cost {
                 continue
             }
-            let old_cost = *costs.get(&cc).unwrap_or(&std::usize::MAX);
-            if !costs.contains_key(&cc) || new_cost < old_cost {
-                parents.insert(cc, pc);
-                costs.insert(cc, new_cost);
+            let penalty = parent.penalty + turn;
+            let state = (child, dir);
+            if states.get(&state).is_none_or(|old| (new_cost, penalty) < *old) {
+                states.insert(state, (new_cost, penalty));
+                state_parents.insert(state, parent.state);
+                if costs.get(&cc).is_none_or(|old| new_cost < *old) {
+                    parents.insert(cc, pc);
+                    costs.insert(cc, new_cost);
+                }
                 let estimate = goal.map_or(0, |g| ctx.heuristic(cc, g));
                 let priority = new_cost + estimate;
-                open.push(Open { coords: child, priority });
+                open.push(Open { state, cost: new_cost, penalty, priority });
             }
         }
     }
+    // The parents of the coordinates only follow the cheapest paths. With a
+    // turn penalty, the path to the goal must follow the states instead, to
+    // also have the fewest turns among the cheapest paths.
+    if let Some(mut state) = reached {
+        while let Some(parent) = state_parents.get(&state) {
+            parents.insert(C::from(state.0), C::from(parent.0));
+            state = *parent;
+        }
+    }
     Tree { root: start, parents, costs }
 }
 
@@ -105,3 +193,415 @@ pub fn path<C: Coords>(
     tree(start, Some(goal), ctx).path(goal)
 }
 
+/// Beginning at the given start coordinates, perform a cost-aware search for
+/// a path to the given goal coordinates or, if the goal is unreachable, to
+/// the reachable coordinates closest to the goal, preferring cheaper paths
+/// among equally close coordinates.
+///
+/// If the goal is unreachable, the search space is exhaustively searched
+/// and must thus be bounded by the context, e.g. via `max_distance`.
+pub fn nearest_reachable<C: Coords>(
+    start: C,
+    goal: C,
+    ctx: &mut impl Context<C>
+) -> Option<Path<C>> {
+    let tree = tree(start, Some(goal), ctx);
+    if tree.cost(goal).is_some() {
+        return tree.path(goal)
+    }
+    // The search for the goal may have stopped early.
+    let tree = self::tree(start, None, ctx);
+    let nearest = tree.iter()
+        .min_by_key(|(c, cost)| (coords::distance(**c, goal), **cost))
+        .map(|(c, _)| *c)?;
+    tree.path(nearest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::coords::{ Direction, FlatTopDiagonal, FlatTopDirection };
+    use crate::search::heuristic;
+    use smallvec::SmallVec;
+    use quickcheck::*;
+
+    struct Uniform;
+
+    impl Context<Cube> for Uniform {
+        fn cost(&mut self, _from: Cube, _to: Cube) -> Option<usize> {
+            Some(1)
+        }
+    }
+
+    #[test]
+    fn prop_tree_with_budget() {
+        fn prop(c: Cube, b1: u8, b2: u8) -> bool {
+            let ctx = &mut Uniform;
+            let (b1, b2) = (b1 % 8, b2 % 8);
+            let t1 = tree_with_budget(c, None, b1 as usize, ctx);
+            let t2 = tree_with_budget(c, None, b2 as usize, ctx);
+            t1.iter().count() == coords::num_in_range(b1 as u16) &&
+            t2.iter().count() == coords::num_in_range(b2 as u16)
+        }
+        quickcheck(prop as fn(_,_,_) -> _);
+    }
+
+    struct Portal {
+        entry: Cube,
+        exit: Cube,
+    }
+
+    impl Context<Cube> for Portal {
+        fn cost(&mut self, _from: Cube, _to: Cube) -> Option<usize> {
+            Some(1)
+        }
+        fn neighbours(&self, c: Cube) -> SmallVec<[Cube; 8]> {
+            let mut ns: SmallVec<[Cube; 8]> = coords::neighbours(c).collect();
+            if c == self.entry {
+                ns.push(self.exit);
+            }
+            ns
+        }
+    }
+
+    #[test]
+    fn test_portal() {
+        let start = Cube::origin();
+        let goal = Cube::new_xy(10, 0);
+        let ctx = &mut Portal { entry: Cube::new_xy(1, 0), exit: Cube::new_xy(9, 0) };
+        let p = path(start, goal, ctx).unwrap();
+        assert_eq!(p.len(), 4);
+        assert_eq!(p.total_cost(), 3);
+        let p = path(start, goal, &mut Uniform).unwrap();
+        assert_eq!(p.len(), 11);
+    }
+
+    struct Counting {
+        expanded: usize,
+        heuristic: fn(Cube, Cube) -> usize,
+    }
+
+    impl Context<Cube> for Counting {
+        fn exit(&mut self, _next: Cube) -> bool {
+            self.expanded += 1;
+            false
+        }
+        fn heuristic(&mut self, from: Cube, to: Cube) -> usize {
+            (self.heuristic)(from, to)
+        }
+        fn cost(&mut self, _from: Cube, _to: Cube) -> Option<usize> {
+            Some(1)
+        }
+    }
+
+    #[test]
+    fn prop_heuristic_zero() {
+        fn prop(c: Cube, d: FlatTopDirection, n: u8) -> bool {
+            let n = n % 8 + 1;
+            let goal = c + d.vector() * n as i32;
+            let zero = &mut Counting { expanded: 0, heuristic: heuristic::zero };
+            let dist = &mut Counting { expanded: 0, heuristic: heuristic::hex_distance };
+            let t_zero = tree(c, Some(goal), zero);
+            let t_dist = tree(c, Some(goal), dist);
+            // Like Dijkstra's algorithm, all coordinates closer than the
+            // goal are expanded before the goal and all costs are optimal.
+            zero.expanded > coords::num_in_range(n as u16 - 1) &&
+            dist.expanded <= zero.expanded &&
+            t_zero.cost(goal) == t_dist.cost(goal) &&
+            t_zero.iter().all(|(x, cost)| coords::distance(c, *x) <= *cost)
+        }
+        quickcheck(prop as fn(_,_,_) -> _);
+    }
+
+    struct Blocked {
+        blocked: Cube,
+    }
+
+    impl Context<Cube> for Blocked {
+        fn max_distance(&self) -> usize {
+            10
+        }
+        fn cost(&mut self, _from: Cube, to: Cube) -> Option<usize> {
+            Some(1).filter(|_| to != self.blocked)
+        }
+    }
+
+    #[test]
+    fn test_nearest_reachable() {
+        let start = Cube::origin();
+        let goal = Cube::new_xy(5, 0);
+        let p = nearest_reachable(start, goal, &mut Blocked { blocked: goal }).unwrap();
+        assert!(path(start, goal, &mut Blocked { blocked: goal }).is_none());
+        assert_eq!(coords::distance(p.last_coords().unwrap(), goal), 1);
+        assert_eq!(p.total_cost(), 4);
+        let p = nearest_reachable(start, goal, &mut Uniform).unwrap();
+        assert_eq!(p.last_coords(), Some(goal));
+    }
+
+    struct Straight {
+        penalty: usize,
+    }
+
+    impl Context<Cube> for Straight {
+        fn turn_penalty(&self) -> usize {
+            self.penalty
+        }
+        fn cost(&mut self, _from: Cube, _to: Cube) -> Option<usize> {
+            Some(1)
+        }
+    }
+
+    fn turns(p: &Path<Cube>) -> usize {
+        let dirs = p.iter().zip(p.iter().skip(1))
+            .map(|(a, b)| b.coords - a.coords)
+            .collect::<Vec<_>>();
+        dirs.windows(2).filter(|w| w[0] != w[1]).count()
+    }
+
+    #[test]
+    fn test_turn_penalty() {
+        let start = Cube::origin();
+        let goal = start + FlatTopDirection::North.vector() * 4
+                         + FlatTopDirection::NorthEast.vector() * 4;
+        let zigzag = path(start, goal, &mut Straight { penalty: 0 }).unwrap();
+        let straight = path(start, goal, &mut Straight { penalty: 1 }).unwrap();
+        assert_eq!(zigzag.len(), straight.len());
+        assert_eq!(turns(&straight), 1);
+        assert!(turns(&straight) < turns(&zigzag));
+        assert_eq!(straight.total_cost(), 8);
+    }
+
+    struct Rough {
+        seed: u8,
+        penalty: usize,
+    }
+
+    impl Context<Cube> for Rough {
+        fn max_distance(&self) -> usize {
+            3
+        }
+        fn turn_penalty(&self) -> usize {
+            self.penalty
+        }
+        fn cost(&mut self, _from: Cube, to: Cube) -> Option<usize> {
+            let h = (to.x() * 31 + to.z() * 17 + 1000) as usize ^ self.seed as usize;
+            Some(1 + h % 3)
+        }
+    }
+
+    /// The cheapest cost and the lowest sum of turn penalties among the
+    /// cheapest paths from the origin to every coordinates within the
+    /// `max_distance` of the context, by exhaustive relaxation.
+    fn brute_force(ctx: &mut Rough) -> HashMap<Cube, (usize, usize)> {
+        let start = Cube::origin();
+        let mut best = HashMap::new();
+        best.insert((start, None), (0, 0));
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for ((c, dir), (cost, penalty)) in best.clone() {
+                for n in coords::neighbours(c) {
+                    if coords::distance(start, n) > ctx.max_distance() {
+                        continue
+                    }
+                    let d = n - c;
+                    let turn = if dir.is_some_and(|x| x != d) { ctx.penalty } else { 0 };
+                    let new = (cost + ctx.cost(c, n).unwrap(), penalty + turn);
+                    if best.get(&(n, Some(d))).is_none_or(|old| new < *old) {
+                        best.insert((n, Some(d)), new);
+                        changed = true;
+                    }
+                }
+            }
+        }
+        let mut optimum = HashMap::new();
+        for ((c, _), v) in best {
+            let o = optimum.entry(c).or_insert(v);
+            *o = std::cmp::min(*o, v);
+        }
+        optimum
+    }
+
+    #[test]
+    fn prop_turn_penalty_optimal() {
+        fn prop(seed: u8, penalty: u8, goal: Cube) -> bool {
+            let penalty = penalty as usize % 3 + 1;
+            let ctx = &mut Rough { seed, penalty };
+            let start = Cube::origin();
+            let optimum = brute_force(ctx);
+            let goal = coords::range(start, 3).nth(goal.x().unsigned_abs() as usize % 37).unwrap();
+            let t = tree(start, Some(goal), ctx);
+            let p = t.path(goal).unwrap();
+            let steps_consistent = p.iter().zip(p.iter().skip(1))
+                .all(|(a, b)| b.cost == a.cost + ctx.cost(a.coords, b.coords).unwrap());
+            let t_all = tree(start, None, ctx);
+            steps_consistent &&
+            (p.total_cost(), turns(&p) * penalty) == optimum[&goal] &&
+            t_all.iter().all(|(c, cost)| *cost == optimum[c].0)
+        }
+        quickcheck(prop as fn(_,_,_) -> _);
+    }
+
+    struct Terrain {
+        mountain: Cube,
+        wall: Cube,
+    }
+
+    impl Context<Cube> for Terrain {
+        fn max_distance(&self) -> usize {
+            4
+        }
+        fn cost(&mut self, _from: Cube, to: Cube) -> Option<usize> {
+            if to == self.wall {
+                None
+            } else if to == self.mountain {
+                Some(10)
+            } else {
+                Some(1)
+            }
+        }
+    }
+
+    #[test]
+    fn test_too_expensive_vs_impassable() {
+        let start = Cube::origin();
+        let ctx = &mut Terrain { mountain: Cube::new_xz(1, 0), wall: Cube::new_xz(-1, 0) };
+        assert_eq!(ctx.cost_ex(start, ctx.mountain), Cost::Passable(10));
+        assert_eq!(ctx.cost_ex(start, ctx.wall), Cost::Impassable);
+        // With a small budget, neither is reached.
+        let t = tree_with_budget(start, None, 5, ctx);
+        assert_eq!(t.cost(ctx.mountain), None);
+        assert_eq!(t.cost(ctx.wall), None);
+        // With a larger budget, the mountain is reached, but never the wall.
+        let t = tree_with_budget(start, None, 20, ctx);
+        assert_eq!(t.cost(ctx.mountain), Some(10));
+        assert_eq!(t.cost(ctx.wall), None);
+    }
+
+    struct Limited {
+        expansions: usize,
+    }
+
+    impl Context<Cube> for Limited {
+        fn max_expansions(&self) -> usize {
+            self.expansions
+        }
+        fn cost(&mut self, _from: Cube, _to: Cube) -> Option<usize> {
+            Some(1)
+        }
+    }
+
+    #[test]
+    fn test_max_expansions() {
+        let start = Cube::origin();
+        let goal = Cube::new_xy(10, 0);
+        let t = tree(start, None, &mut Limited { expansions: 0 });
+        assert_eq!(t.iter().count(), 1);
+        let t = tree(start, None, &mut Limited { expansions: 1 });
+        assert_eq!(t.iter().count(), 7);
+        let t = tree(start, Some(goal), &mut Limited { expansions: 3 });
+        assert!(t.iter().count() <= 1 + 3 * 6);
+        assert_eq!(t.cost(goal), None);
+        assert!(path(start, goal, &mut Limited { expansions: 100 }).is_some());
+    }
+
+    struct Diagonals;
+
+    impl Context<Cube> for Diagonals {
+        fn include_diagonals(&self) -> bool {
+            true
+        }
+        fn cost(&mut self, _from: Cube, _to: Cube) -> Option<usize> {
+            Some(1)
+        }
+    }
+
+    #[test]
+    fn test_include_diagonals() {
+        let start = Cube::origin();
+        let goal = start + FlatTopDiagonal::East.vector() * 3;
+        assert_eq!(coords::distance(start, goal), 6);
+        let p = path(start, goal, &mut Uniform).unwrap();
+        assert_eq!(p.total_cost(), 6);
+        let p = path(start, goal, &mut Diagonals).unwrap();
+        assert_eq!(p.total_cost(), 3);
+        assert_eq!(p.len(), 4);
+        // Two diagonal moves bridge a distance of 3 along a regular axis.
+        let goal = start + FlatTopDirection::North.vector() * 3;
+        assert_eq!(path(start, goal, &mut Diagonals).unwrap().total_cost(), 2);
+        assert_eq!(path(start, goal, &mut Uniform).unwrap().total_cost(), 3);
+    }
+
+    struct Capped {
+        expanded: Vec<Cube>,
+    }
+
+    impl Context<Cube> for Capped {
+        fn max_cost(&self) -> usize {
+            3
+        }
+        fn exit(&mut self, next: Cube) -> bool {
+            self.expanded.push(next);
+            false
+        }
+        fn cost(&mut self, _from: Cube, _to: Cube) -> Option<usize> {
+            Some(1)
+        }
+    }
+
+    #[test]
+    fn test_capped_search_short_circuit() {
+        let start = Cube::origin();
+        let ctx = &mut Capped { expanded: Vec::new() };
+        let t = tree(start, None, ctx);
+        // Without a goal, the search space within the budget is drained.
+        assert_eq!(ctx.expanded.len(), coords::num_in_range(3));
+        assert!(ctx.expanded.iter().all(|c| t.cost(*c).unwrap() <= 3));
+        // With a goal beyond the budget, the search stops as soon
+        // as the goal is known to be out of reach.
+        let ctx = &mut Capped { expanded: Vec::new() };
+        let far = Cube::new_xz(10, 0);
+        let t = tree(start, Some(far), ctx);
+        assert_eq!(t.cost(far), None);
+        assert_eq!(ctx.expanded, vec![start]);
+        // A goal within the budget is unaffected.
+        let ctx = &mut Capped { expanded: Vec::new() };
+        let near = Cube::new_xz(3, 0);
+        assert_eq!(tree(start, Some(near), ctx).cost(near), Some(3));
+        assert!(ctx.expanded.len() < coords::num_in_range(3));
+    }
+
+    struct Weighted {
+        weight: usize,
+    }
+
+    impl Context<Cube> for Weighted {
+        fn max_cost(&self) -> usize {
+            10
+        }
+        fn heuristic(&mut self, from: Cube, to: Cube) -> usize {
+            heuristic::weighted(from, to, self.weight)
+        }
+        fn cost(&mut self, _from: Cube, _to: Cube) -> Option<usize> {
+            Some(2)
+        }
+    }
+
+    #[test]
+    fn test_capped_search_weighted() {
+        let start = Cube::origin();
+        let goal = Cube::new_xz(5, 0);
+        let far = Cube::new_xz(6, 0);
+        // A weight up to the minimum cost of a step is admissible,
+        // so a goal within the budget is found.
+        for weight in 0 ..= 2 {
+            let ctx = &mut Weighted { weight };
+            assert_eq!(tree(start, Some(goal), ctx).cost(goal), Some(10));
+            assert_eq!(tree(start, Some(far), ctx).cost(far), None);
+        }
+        // An overestimating heuristic makes the goal seem out of reach.
+        let ctx = &mut Weighted { weight: 3 };
+        assert_eq!(tree(start, Some(goal), ctx).cost(goal), None);
+        assert_eq!(tree(start, None, ctx).cost(goal), Some(10));
+    }
+}
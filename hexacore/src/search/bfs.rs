@@ -4,7 +4,6 @@ use std::collections::VecDeque;
 use super::{ Context, Tree, Path };
 
 use crate::grid::Coords;
-use crate::grid::coords;
 
 /// Beginning at the given start coordinates, perform a breadth-first-search
 /// across the grid, subject to the constraints of the given options, returning
@@ -27,20 +26,18 @@ pub fn tree<C: Coords>(
     let max_distance = ctx.max_distance();
     let mut parents  = HashMap::new();
     let mut front    = VecDeque::new();
-    front.push_back((start.into(), 0));
-    while let Some((c,d)) = front.pop_front() {
-        let cc = C::from(c);
+    front.push_back((start, 0));
+    while let Some((cc,d)) = front.pop_front() {
         if ctx.exit(cc) || goal.map_or(false, |g| g == cc) {
             break
         }
-        for n in coords::neighbours(c) {
-            let nc = C::from(n);
+        for nc in ctx.neighbours(cc) {
             if d < max_distance
                 && !parents.contains_key(&nc)
-                && ctx.cost(cc, nc).map_or(false, |cost| cost <= max_cost)
+                && ctx.cost_ex(cc, nc).passable().map_or(false, |cost| cost <= max_cost)
             {
                 parents.insert(nc, cc);
-                front.push_back((n, d+1));
+                front.push_back((nc, d+1));
             }
         }
     }
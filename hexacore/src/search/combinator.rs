@@ -0,0 +1,187 @@
+//! Composable search contexts, for assembling the movement rules of a
+//! search from independent layers, e.g. terrain costs, tiles occupied by
+//! entities and the extent of the grid:
+//!
+//! ```raw
+//! layered(terrain, layered(blocked_by(occupied), within_grid(&grid)))
+//! ```
+//!
+//! The costs of layers add up and coordinates are impassable if they are
+//! impassable in any layer. The layers [`blocked_by`] and [`within_grid`]
+//! only restrict passability and add no costs.
+
+use crate::grid::{ Coords, Grid };
+
+use super::{ Context, Cost };
+
+use smallvec::SmallVec;
+
+use std::collections::HashSet;
+
+/// Two contexts layered on top of each other, see [`layered`].
+pub struct Layered<A, B> {
+    pub base: A,
+    pub layer: B,
+}
+
+/// Layer two contexts on top of each other, such that the cost of a move
+/// is the sum of its costs in both contexts. Coordinates are impassable
+/// if they are impassable in either context and the bounds of the search
+/// are the tighter bounds of both contexts. The adjacency of coordinates,
+/// including whether diagonal moves are permitted, is determined by the
+/// base context.
+pub fn layered<C, A, B>(base: A, layer: B) -> Layered<A, B>
+where
+    C: Coords,
+    A: Context<C>,
+    B: Context<C>
+{
+    Layered { base, layer }
+}
+
+impl<C, A, B> Context<C> for Layered<A, B>
+where
+    C: Coords,
+    A: Context<C>,
+    B: Context<C>
+{
+    fn max_cost(&self) -> usize {
+        self.base.max_cost().min(self.layer.max_cost())
+    }
+    fn max_distance(&self) -> usize {
+        self.base.max_distance().min(self.layer.max_distance())
+    }
+    fn max_expansions(&self) -> usize {
+        self.base.max_expansions().min(self.layer.max_expansions())
+    }
+    fn exit(&mut self, next: C) -> bool {
+        self.base.exit(next) || self.layer.exit(next)
+    }
+    fn heuristic(&mut self, from: C, to: C) -> usize {
+        // The maximum of admissible heuristics is admissible
+        // for the sum of the costs.
+        self.base.heuristic(from, to).max(self.layer.heuristic(from, to))
+    }
+    fn neighbours(&self, c: C) -> SmallVec<[C; 8]> {
+        self.base.neighbours(c)
+    }
+    fn include_diagonals(&self) -> bool {
+        self.base.include_diagonals()
+    }
+    fn turn_penalty(&self) -> usize {
+        self.base.turn_penalty() + self.layer.turn_penalty()
+    }
+    fn cost(&mut self, from: C, to: C) -> Option<usize> {
+        self.cost_ex(from, to).passable()
+    }
+    fn cost_ex(&mut self, from: C, to: C) -> Cost {
+        match (self.base.cost_ex(from, to), self.layer.cost_ex(from, to)) {
+            (Cost::Passable(a), Cost::Passable(b)) => Cost::Passable(a + b),
+            _ => Cost::Impassable
+        }
+    }
+}
+
+/// A context in which the given coordinates are impassable,
+/// see [`blocked_by`].
+pub struct BlockedBy<C> {
+    pub blocked: HashSet<C>,
+}
+
+/// A context in which the given coordinates are impassable, e.g.
+/// because they are occupied, and all other coordinates are passable
+/// at no cost.
+pub fn blocked_by<C: Coords>(blocked: HashSet<C>) -> BlockedBy<C> {
+    BlockedBy { blocked }
+}
+
+impl<C: Coords> Context<C> for BlockedBy<C> {
+    fn heuristic(&mut self, _from: C, _to: C) -> usize {
+        0
+    }
+    fn cost(&mut self, _from: C, to: C) -> Option<usize> {
+        Some(0).filter(|_| !self.blocked.contains(&to))
+    }
+}
+
+/// A context in which only the coordinates of a grid are passable,
+/// see [`within_grid`].
+pub struct WithinGrid<'a, C: Coords, T> {
+    pub grid: &'a Grid<C, T>,
+}
+
+/// A context in which the coordinates of the given grid are passable
+/// at no cost and all other coordinates are impassable.
+pub fn within_grid<C: Coords, T>(grid: &Grid<C, T>) -> WithinGrid<'_, C, T> {
+    WithinGrid { grid }
+}
+
+impl<'a, C: Coords, T> Context<C> for WithinGrid<'a, C, T> {
+    fn heuristic(&mut self, _from: C, _to: C) -> usize {
+        0
+    }
+    fn cost(&mut self, _from: C, to: C) -> Option<usize> {
+        self.grid.get(to).map(|_| 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::{ Orientation, Schema, SideLength };
+    use crate::grid::{ Cube, shape };
+    use crate::search::{ astar, reachable_count, reachable_count_estimate };
+
+    struct Uniform(usize);
+
+    impl Context<Cube> for Uniform {
+        fn cost(&mut self, _from: Cube, _to: Cube) -> Option<usize> {
+            Some(self.0)
+        }
+    }
+
+    #[test]
+    fn test_layered() {
+        let start = Cube::origin();
+        let goal = Cube::new_xz(3, 0);
+        let ctx = &mut layered(Uniform(1), Uniform(2));
+        assert_eq!(ctx.cost(start, goal), Some(3));
+        assert_eq!(astar::path(start, goal, ctx).unwrap().total_cost(), 9);
+
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid = Grid::<Cube>::new(schema, shape::hexagon(5));
+        let blocked = Cube::new_xz(1, 0);
+        let ctx = &mut layered(Uniform(1),
+            layered(blocked_by(vec![blocked].into_iter().collect()), within_grid(&grid)));
+        assert_eq!(ctx.cost_ex(start, blocked), Cost::Impassable);
+        assert_eq!(ctx.cost_ex(start, Cube::new_xz(6, 0)), Cost::Impassable);
+        let p = astar::path(start, goal, ctx).unwrap();
+        assert_eq!(p.total_cost(), 4);
+        assert!(p.coords().all(|c| c != blocked && grid.get(c).is_some()));
+        assert!(astar::path(start, Cube::new_xz(6, 0), ctx).is_none());
+    }
+
+    struct Diagonals;
+
+    impl Context<Cube> for Diagonals {
+        fn include_diagonals(&self) -> bool {
+            true
+        }
+        fn cost(&mut self, _from: Cube, _to: Cube) -> Option<usize> {
+            Some(1)
+        }
+    }
+
+    #[test]
+    fn test_layered_diagonals() {
+        let start = Cube::origin();
+        let ctx = &mut layered(Diagonals, Uniform(0));
+        assert!(ctx.include_diagonals());
+        assert!(!layered(Uniform(0), Diagonals).include_diagonals());
+        for budget in 0 .. 5 {
+            let count = reachable_count(start, budget, ctx);
+            assert!(count <= reachable_count_estimate(start, budget, ctx));
+        }
+        assert_eq!(reachable_count(start, 1, ctx), 13);
+    }
+}
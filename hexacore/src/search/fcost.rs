@@ -0,0 +1,212 @@
+//! Cost-aware search with fractional (`f32`) costs, e.g. for terrain
+//! weights like `1.5` for rough terrain, which the integer costs of the
+//! [`Context`](super::Context) of the other searches cannot express.
+//!
+//! The API mirrors that of [`astar`](super::astar) with integer costs.
+
+use std::cmp::Ordering;
+use std::collections::{ BinaryHeap, HashMap };
+
+use crate::grid::Coords;
+use crate::grid::coords;
+
+use super::heuristic;
+
+use smallvec::SmallVec;
+
+/// The context of a search with fractional costs, see
+/// [`search::Context`](super::Context).
+pub trait Context<C: Coords> {
+    /// The maximum total cost of the paths of a search, i.e. its budget.
+    fn max_cost(&self) -> f32 {
+        f32::INFINITY
+    }
+    fn max_distance(&self) -> usize {
+        usize::MAX
+    }
+    /// An estimate of the cost of reaching the given goal coordinates,
+    /// which must not exceed the actual cost for the search to find
+    /// optimal paths.
+    ///
+    /// By default, this is the distance between the coordinates, which
+    /// assumes that every step costs at least 1. Contexts with cheaper
+    /// steps must scale the estimate accordingly.
+    fn heuristic(&mut self, from: C, to: C) -> f32 {
+        heuristic::hex_distance(from, to) as f32
+    }
+    /// The coordinates that are adjacent to the given coordinates in
+    /// the search space, which are by default the neighbouring coordinates.
+    fn neighbours(&self, c: C) -> SmallVec<[C; 8]> {
+        coords::neighbours(c).collect()
+    }
+    /// The cost of moving from the given coordinates to the given adjacent
+    /// coordinates, or `None` if the latter are impassable. Costs must not
+    /// be negative.
+    fn cost(&mut self, from: C, to: C) -> Option<f32>;
+}
+
+/// The result of a search with fractional costs, see
+/// [`search::Tree`](super::Tree).
+pub struct Tree<C> {
+    root: C,
+    parents: HashMap<C, C>,
+    costs: HashMap<C, f32>,
+}
+
+impl<C: Coords> Tree<C> {
+    pub fn root(&self) -> C {
+        self.root
+    }
+
+    /// Get the total cost of the path from the root node to the given
+    /// coordinates, if it exists.
+    pub fn cost(&self, coords: C) -> Option<f32> {
+        self.costs.get(&coords).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&C, &f32)> {
+        self.costs.iter()
+    }
+
+    /// Trace a path from the given goal back to the root of the tree,
+    /// yielding the coordinates of the path from start to goal together
+    /// with the total cost of reaching them.
+    pub fn path(&self, goal: C) -> Option<Vec<(C, f32)>> {
+        let mut path = vec![(goal, self.cost(goal)?)];
+        let mut current = goal;
+        while current != self.root {
+            let parent = *self.parents.get(&current)?;
+            path.push((parent, self.cost(parent).unwrap_or(0.)));
+            current = parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// A node in the "open" list of the search to prioritise the search.
+struct Open<C> {
+    coords: C,
+    cost: f32,
+    priority: f32,
+}
+
+impl<C> PartialEq for Open<C> {
+    fn eq(&self, other: &Open<C>) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<C> Eq for Open<C> {}
+
+impl<C> PartialOrd for Open<C> {
+    fn partial_cmp(&self, other: &Open<C>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<C> Ord for Open<C> {
+    fn cmp(&self, other: &Open<C>) -> Ordering {
+        // Lower priorities (= estimated total costs)
+        // are considered "greater" for the binary heap.
+        other.priority.total_cmp(&self.priority)
+    }
+}
+
+/// Beginning at the given start coordinates, perform a cost-aware search
+/// with fractional costs, like [`astar::tree`](super::astar::tree).
+pub fn tree<C: Coords>(
+    start: C,
+    goal: Option<C>,
+    ctx: &mut impl Context<C>
+) -> Tree<C> {
+    let max_cost     = ctx.max_cost();
+    let max_distance = ctx.max_distance();
+    let mut parents  = HashMap::new();
+    let mut costs    = HashMap::new();
+    let mut open     = BinaryHeap::new();
+    open.push(Open { coords: start, cost: 0., priority: 0. });
+    costs.insert(start, 0.);
+    while let Some(parent) = open.pop() {
+        let pc = parent.coords;
+        if matches!(costs.get(&pc), Some(c) if *c < parent.cost) {
+            // The coordinates have been expanded at a lower cost already.
+            continue
+        }
+        if goal.is_some() && parent.priority > max_cost {
+            break
+        }
+        if goal == Some(pc) {
+            break
+        }
+        for cc in ctx.neighbours(pc) {
+            if coords::distance(cc, start) > max_distance {
+                continue
+            }
+            let new_cost = match ctx.cost(pc, cc) {
+                Some(cost) => parent.cost + cost,
+                None => continue
+            };
+            if new_cost > max_cost {
+                continue
+            }
+            if costs.get(&cc).is_none_or(|old| new_cost < *old) {
+                parents.insert(cc, pc);
+                costs.insert(cc, new_cost);
+                let estimate = goal.map_or(0., |g| ctx.heuristic(cc, g));
+                open.push(Open { coords: cc, cost: new_cost, priority: new_cost + estimate });
+            }
+        }
+    }
+    Tree { root: start, parents, costs }
+}
+
+/// Beginning at the given start coordinates, perform a cost-aware search
+/// with fractional costs for a path to the given goal coordinates, like
+/// [`astar::path`](super::astar::path).
+pub fn path<C: Coords>(
+    start: C,
+    goal: C,
+    ctx: &mut impl Context<C>
+) -> Option<Vec<(C, f32)>> {
+    tree(start, Some(goal), ctx).path(goal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Cube;
+
+    /// Rough terrain costs 1.5, slightly rough terrain 1.4.
+    struct Terrain;
+
+    impl Context<Cube> for Terrain {
+        fn max_distance(&self) -> usize {
+            4
+        }
+        fn cost(&mut self, _from: Cube, to: Cube) -> Option<f32> {
+            if to == Cube::new_xz(1, -1) {
+                Some(1.5)
+            } else if to == Cube::new_xz(1, 0) {
+                Some(1.4)
+            } else {
+                Some(1.)
+            }
+        }
+    }
+
+    #[test]
+    fn test_fractional_costs() {
+        // Both shortest paths to the goal cross one of the rough tiles,
+        // which have the same cost if rounded to integers.
+        let (start, goal) = (Cube::origin(), Cube::new_xz(2, -1));
+        let path = path(start, goal, &mut Terrain).unwrap();
+        let coords = path.iter().map(|(c, _)| *c).collect::<Vec<_>>();
+        assert_eq!(coords, vec![start, Cube::new_xz(1, 0), goal]);
+        assert!((path[2].1 - 2.4).abs() < 1e-6);
+        let tree = tree(start, None, &mut Terrain);
+        assert!((tree.cost(Cube::new_xz(1, -1)).unwrap() - 1.5).abs() < 1e-6);
+        assert_eq!(tree.cost(goal), Some(path[2].1));
+        assert_eq!(tree.iter().count(), coords::num_in_range(4));
+    }
+}
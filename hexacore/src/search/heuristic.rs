@@ -0,0 +1,34 @@
+
+//! Heuristics for the A* search, for use in [`Context::heuristic`].
+//!
+//! For the A* search to find optimal (i.e. cheapest) paths, the heuristic
+//! must be _admissible_, i.e. it must never overestimate the cost of
+//! reaching the goal.
+//!
+//! [`Context::heuristic`]: ../trait.Context.html#method.heuristic
+
+use crate::grid::coords::{ self, Coords };
+
+/// The (beeline) distance between coordinates, which is an admissible
+/// heuristic if the cost of every step is at least `1`. This is the
+/// default heuristic of a search [`Context`](../trait.Context.html).
+pub fn hex_distance<C: Coords>(from: C, to: C) -> usize {
+    coords::distance(from, to)
+}
+
+/// A heuristic that never estimates any cost, which is always
+/// admissible and turns the A* search into Dijkstra's algorithm.
+pub fn zero<C: Coords>(_from: C, _to: C) -> usize {
+    0
+}
+
+/// The (beeline) distance between coordinates, weighted by the given
+/// cost per step. The heuristic is admissible if the weight does not
+/// exceed the minimum cost of any step, in which case it is more
+/// precise than [`hex_distance`] for weights greater than `1`. Otherwise,
+/// the A* search may not only find more expensive paths, but also miss
+/// goals within the `max_cost` of the context, since it stops as soon as
+/// the estimated cost of reaching the goal exceeds the `max_cost`.
+pub fn weighted<C: Coords>(from: C, to: C, weight: usize) -> usize {
+    coords::distance(from, to) * weight
+}
@@ -0,0 +1,169 @@
+//! Regression tests for the searches on grids with known obstacle layouts,
+//! asserting the exact costs of optimal paths, as well as a benchmark of
+//! the A* search on a large grid, run with
+//!
+//! ```raw
+//! cargo test --release -p hexacore bench_ -- --ignored --nocapture
+//! ```
+
+use super::*;
+use crate::geo::{ Orientation, Rotation, Schema, SideLength };
+use crate::grid::{ Axial, Cube, FlatTopDirection, Grid, shape };
+
+use std::collections::HashSet;
+use std::time::Instant;
+
+/// A map given as rows of characters, whereby the `q` and `r` axial
+/// coordinates of a tile are its column and row. A `#` is an obstacle,
+/// a digit is the cost of entering a tile and all other characters are
+/// tiles with a cost of `1`.
+struct Map {
+    grid: Grid<Axial, usize>,
+    blocked: HashSet<Axial>,
+    heuristic: fn(Axial, Axial) -> usize,
+}
+
+impl Map {
+    fn parse(rows: &[&str]) -> Map {
+        let schema = Schema::new(SideLength(10.), Orientation::PointyTop);
+        let tiles = rows.iter().enumerate().flat_map(|(r, row)| {
+            row.chars().enumerate().map(move |(q, t)| (Axial { col: q as i32, row: r as i32 }, t))
+        }).collect::<Vec<_>>();
+        let data = tiles.iter().map(|(c, _)| Cube::from(*c)).collect::<Vec<_>>();
+        let total = data.len();
+        let mut grid = Grid::new(schema, shape::Shape { data, total });
+        let mut blocked = HashSet::new();
+        for (c, t) in tiles {
+            if t == '#' {
+                blocked.insert(c);
+            }
+            grid.set_payload(c, t.to_digit(10).unwrap_or(1) as usize);
+        }
+        Map { grid, blocked, heuristic: heuristic::hex_distance }
+    }
+
+    fn dijkstra(mut self) -> Map {
+        self.heuristic = heuristic::zero;
+        self
+    }
+}
+
+impl Context<Axial> for Map {
+    fn heuristic(&mut self, from: Axial, to: Axial) -> usize {
+        (self.heuristic)(from, to)
+    }
+    fn cost(&mut self, _from: Axial, to: Axial) -> Option<usize> {
+        self.grid.payload(to).copied().filter(|_| !self.blocked.contains(&to))
+    }
+}
+
+const WALL: [&str; 7] =
+    [ "..#...."
+    , "..#...."
+    , "..#.#.."
+    , "..#.#.."
+    , "....#.."
+    , "....#.."
+    , "....#.."
+    ];
+
+const SWAMP: [&str; 5] =
+    [ "......."
+    , ".99999."
+    , ".9...9."
+    , ".99.99."
+    , "......."
+    ];
+
+fn cost(map: &mut Map, from: (i32, i32), to: (i32, i32)) -> Option<usize> {
+    let (from, to) = (Axial { col: from.0, row: from.1 }, Axial { col: to.0, row: to.1 });
+    astar::path(from, to, map).map(|p| p.total_cost())
+}
+
+fn steps(map: &mut Map, from: (i32, i32), to: (i32, i32)) -> Option<usize> {
+    let (from, to) = (Axial { col: from.0, row: from.1 }, Axial { col: to.0, row: to.1 });
+    bfs::path(from, to, map).map(|p| p.len() - 1)
+}
+
+#[test]
+fn test_astar_wall() {
+    let map = &mut Map::parse(&WALL);
+    // Around the bottom of the first wall segment.
+    assert_eq!(cost(map, (0, 0), (3, 0)), Some(10));
+    // Around the top of the second wall segment.
+    assert_eq!(cost(map, (0, 6), (6, 0)), Some(7));
+    // Around the bottom of the first and the top of the second segment.
+    assert_eq!(cost(map, (0, 0), (6, 6)), Some(16));
+    // Unobstructed.
+    assert_eq!(cost(map, (0, 6), (3, 4)), Some(3));
+    // Obstacles are never reachable.
+    assert_eq!(cost(map, (0, 0), (2, 0)), None);
+}
+
+#[test]
+fn test_astar_swamp() {
+    let map = &mut Map::parse(&SWAMP);
+    // The only entry to the inner region without crossing
+    // the swamp is from the bottom.
+    assert_eq!(cost(map, (3, 4), (3, 2)), Some(2));
+    assert_eq!(cost(map, (0, 0), (3, 2)), Some(8));
+    // Even from the upper right, crossing the swamp is more
+    // expensive than the detour through the entry.
+    assert_eq!(cost(map, (5, 0), (4, 2)), Some(9));
+}
+
+#[test]
+fn test_bfs_wall() {
+    let map = &mut Map::parse(&WALL);
+    assert_eq!(steps(map, (0, 0), (3, 0)), Some(10));
+    assert_eq!(steps(map, (0, 6), (6, 0)), Some(7));
+    assert_eq!(steps(map, (0, 0), (6, 6)), Some(16));
+    assert_eq!(steps(map, (0, 0), (2, 0)), None);
+    // The BFS ignores the costs.
+    let map = &mut Map::parse(&SWAMP);
+    assert_eq!(steps(map, (5, 0), (4, 2)), Some(2));
+}
+
+#[test]
+fn test_astar_dijkstra_equal_cost() {
+    for rows in &[&WALL[..], &SWAMP[..]] {
+        let map = &mut Map::parse(rows);
+        let dijkstra = &mut Map::parse(rows).dijkstra();
+        let tiles = map.grid.iter().map(|(c,_)| *c).collect::<Vec<_>>();
+        for from in &tiles {
+            for to in &tiles {
+                let a = astar::path(*from, *to, map).map(|p| p.total_cost());
+                let d = astar::path(*from, *to, dijkstra).map(|p| p.total_cost());
+                assert_eq!(a, d, "{} -> {}", from, to);
+            }
+        }
+    }
+}
+
+struct Uniform;
+
+impl Context<Cube> for Uniform {
+    fn cost(&mut self, _from: Cube, _to: Cube) -> Option<usize> {
+        Some(1)
+    }
+}
+
+#[test]
+#[ignore]
+fn bench_astar_large_grid() {
+    let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+    let grid = Grid::<Cube>::new(schema, shape::hexagon(100));
+    // A ring-shaped wall with a single gap.
+    let blocked = coords::walk_ring(Cube::origin(), FlatTopDirection::North, 50, Rotation::CW)
+        .skip(1)
+        .collect();
+    let mut ctx = combinator::layered(Uniform,
+        combinator::layered(combinator::blocked_by(blocked), combinator::within_grid(&grid)));
+    let (start, goal) = (Cube::new_xz(-20, 10), Cube::new_xz(80, -10));
+    let now = Instant::now();
+    let n = 10;
+    for _ in 0 .. n {
+        assert!(astar::path(start, goal, &mut ctx).is_some());
+    }
+    println!("astar: {:?} per path", now.elapsed() / n);
+}
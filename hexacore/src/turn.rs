@@ -0,0 +1,166 @@
+
+//! Scheduling of turns for turn-based games.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// A scheduler for the turns of entities, ordered by the time at which
+/// entities act and, at the same time, by their initiative.
+///
+/// Entities with higher initiative act first. Entities with the same
+/// initiative act in the order in which they were (re)scheduled.
+pub struct Scheduler<E> {
+    queue: BinaryHeap<Scheduled<E>>,
+    /// The current time, i.e. the time of the last turn.
+    time: u64,
+    /// The initiative of the entity whose turn it currently is.
+    active: Option<i32>,
+    /// A sequence number to preserve the order of scheduling
+    /// for entities with the same time and initiative.
+    seq: u64,
+}
+
+struct Scheduled<E> {
+    time: u64,
+    initiative: i32,
+    seq: u64,
+    entity: E,
+}
+
+impl<E> PartialEq for Scheduled<E> {
+    fn eq(&self, other: &Scheduled<E>) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<E> Eq for Scheduled<E> {}
+
+impl<E> PartialOrd for Scheduled<E> {
+    fn partial_cmp(&self, other: &Scheduled<E>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<E> Ord for Scheduled<E> {
+    fn cmp(&self, other: &Scheduled<E>) -> Ordering {
+        // Earlier times, higher initiatives and lower sequence
+        // numbers are considered "greater" for the binary heap.
+        other.time.cmp(&self.time)
+            .then(self.initiative.cmp(&other.initiative))
+            .then(other.seq.cmp(&self.seq))
+    }
+}
+
+impl<E> Default for Scheduler<E> {
+    fn default() -> Self {
+        Scheduler::new()
+    }
+}
+
+impl<E> Scheduler<E> {
+    /// Create a new, empty scheduler.
+    pub fn new() -> Scheduler<E> {
+        Scheduler {
+            queue: BinaryHeap::new(),
+            time: 0,
+            active: None,
+            seq: 0,
+        }
+    }
+
+    /// The current time, i.e. the time of the current turn.
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// The number of scheduled entities.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Schedule an entity with the given initiative to act at
+    /// the current time.
+    pub fn add(&mut self, entity: E, initiative: i32) {
+        self.schedule(entity, initiative, 0)
+    }
+
+    /// Reschedule the entity whose turn it currently is, i.e. the entity
+    /// last returned from [`Scheduler::next`], to act again after the
+    /// given delay, retaining its initiative.
+    ///
+    /// Panics if there is no entity whose turn it currently is.
+    pub fn requeue(&mut self, entity: E, delay: u64) {
+        let initiative = self.active.take().expect("No active entity.");
+        self.schedule(entity, initiative, delay)
+    }
+
+    fn schedule(&mut self, entity: E, initiative: i32, delay: u64) {
+        let time = self.time + delay;
+        let seq = self.seq;
+        self.seq += 1;
+        self.queue.push(Scheduled { time, initiative, seq, entity })
+    }
+}
+
+impl<E> Iterator for Scheduler<E> {
+    type Item = E;
+
+    /// Take the entity whose turn is next, advancing the current time
+    /// to the time of its turn.
+    fn next(&mut self) -> Option<E> {
+        self.queue.pop().map(|s| {
+            self.time = s.time;
+            self.active = Some(s.initiative);
+            s.entity
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initiative() {
+        let mut s = Scheduler::new();
+        s.add("slow", 1);
+        s.add("fast", 10);
+        s.add("medium", 5);
+        s.add("medium2", 5);
+        assert_eq!(s.next(), Some("fast"));
+        assert_eq!(s.next(), Some("medium"));
+        assert_eq!(s.next(), Some("medium2"));
+        assert_eq!(s.next(), Some("slow"));
+        assert_eq!(s.next(), None);
+    }
+
+    #[test]
+    fn test_requeue() {
+        let mut s = Scheduler::new();
+        s.add("a", 3);
+        s.add("b", 2);
+        s.add("c", 1);
+        // "a" acts and must wait two ticks.
+        assert_eq!(s.next(), Some("a"));
+        s.requeue("a", 2);
+        // "b" acts and must wait one tick.
+        assert_eq!(s.next(), Some("b"));
+        s.requeue("b", 1);
+        assert_eq!(s.next(), Some("c"));
+        s.requeue("c", 2);
+        assert_eq!(s.next(), Some("b"));
+        assert_eq!(s.time(), 1);
+        s.requeue("b", 1);
+        // "a" and "c" act at time 2 and "a" has the higher initiative,
+        // just as "b".
+        assert_eq!(s.next(), Some("a"));
+        assert_eq!(s.next(), Some("b"));
+        assert_eq!(s.next(), Some("c"));
+        assert_eq!(s.time(), 2);
+        assert!(s.is_empty());
+    }
+}
@@ -1,17 +1,36 @@
 
 //! A generic UI state model for scrollable view of hexagonal grids.
 
-use crate::geo::{ Bounds, Hexagon };
-use crate::grid::{ Grid, Coords };
+use crate::geo::{ Bounds, Hexagon, Schema };
+use crate::grid::{ Grid, Coords, Cube };
+use crate::grid::shape::Shape;
 use crate::ui::scroll;
 
 use nalgebra::Point2;
+use std::collections::{ BTreeMap, HashMap };
 
-/// The state of a scrollable grid view.
+/// The state of a scrollable and zoomable grid view.
 pub struct State<C: Coords> {
     grid: Grid<C>,
+    /// The visible part of the grid, in pixel coordinates of the grid.
     viewport: Bounds,
     position: Point2<f32>,
+    /// The number of screen pixels per pixel of the grid.
+    scale: f32,
+    insets: Insets,
+    snap: bool,
+    hover: Option<C>,
+    selected: Option<C>,
+}
+
+/// The space reserved around a grid view within the window, in pixels,
+/// e.g. for HUD panels.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Insets {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
 }
 
 impl<C: Coords> State<C> {
@@ -27,6 +46,11 @@ impl<C: Coords> State<C> {
                 width: bounds.width,
                 height: bounds.height
             },
+            scale: 1.,
+            insets: Insets::default(),
+            snap: false,
+            hover: None,
+            selected: None,
         }
     }
 
@@ -38,12 +62,83 @@ impl<C: Coords> State<C> {
         &self.grid
     }
 
+    /// The width of the view on the screen.
     pub fn width(&self) -> f32 {
-        self.viewport.width
+        self.viewport.width * self.scale
     }
 
+    /// The height of the view on the screen.
     pub fn height(&self) -> f32 {
-        self.viewport.height
+        self.viewport.height * self.scale
+    }
+
+    /// The zoom level of the view, i.e. the number of screen pixels per
+    /// pixel of the grid, by which the grid is to be scaled when drawn.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Zoom the view to the given scale, keeping the anchor of the scaling,
+    /// given in screen coordinates, fixed on the screen, e.g. the position
+    /// of the cursor, see [`scroll::Zoom`].
+    pub fn zoom(&mut self, scaling: scroll::Scaling) {
+        if scaling.scale <= 0. {
+            return
+        }
+        let (width, height) = (self.width(), self.height());
+        let anchor = (scaling.anchor - self.position) / self.scale;
+        let target = self.viewport.position + anchor;
+        self.scale = scaling.scale;
+        self.viewport.width  = width / self.scale;
+        self.viewport.height = height / self.scale;
+        self.viewport.position = target - (scaling.anchor - self.position) / self.scale;
+        self.scroll(scroll::Delta { dx: 0.0, dy: 0.0 });
+    }
+
+    /// Zoom out (or in) just far enough for the whole grid to fit within
+    /// the view and center the grid in the view, e.g. for an overview.
+    pub fn fit(&mut self) {
+        let grid = self.grid.dimensions();
+        let (width, height) = (self.width(), self.height());
+        if grid.width <= 0. || grid.height <= 0. || width <= 0. || height <= 0. {
+            return
+        }
+        self.scale = f32::min(width / grid.width, height / grid.height);
+        self.viewport.width  = width / self.scale;
+        self.viewport.height = height / self.scale;
+        self.scroll(scroll::Delta { dx: 0.0, dy: 0.0 });
+    }
+
+    pub fn insets(&self) -> &Insets {
+        &self.insets
+    }
+
+    /// Set the insets of the view within the window, which take effect
+    /// with the next call to [`State::resize_window`]. The insets do not
+    /// affect [`State::resize`].
+    pub fn set_insets(&mut self, left: f32, top: f32, right: f32, bottom: f32) {
+        self.insets = Insets { left, top, right, bottom };
+    }
+
+    /// The coordinates of the hovered tile, if any.
+    pub fn hover(&self) -> Option<C> {
+        self.hover
+    }
+
+    /// Set or clear the coordinates of the hovered tile, e.g. as
+    /// determined by [`State::from_pixel`] for the mouse position.
+    pub fn set_hover(&mut self, c: Option<C>) {
+        self.hover = c;
+    }
+
+    /// The coordinates of the selected tile, if any.
+    pub fn selected(&self) -> Option<C> {
+        self.selected
+    }
+
+    /// Set or clear the coordinates of the selected tile.
+    pub fn set_selected(&mut self, c: Option<C>) {
+        self.selected = c;
     }
 
     /// Get a reference to the bounds of the viewport. The position
@@ -67,7 +162,7 @@ impl<C: Coords> State<C> {
         if !bounds.contains(p) {
             return None
         }
-        self.grid.from_pixel(p - self.position.coords + self.viewport.position.coords)
+        self.grid.from_pixel(self.viewport.position + (p - self.position) / self.scale)
     }
 
     /// Get an iterator over the hexagons currently in the viewport.
@@ -75,7 +170,9 @@ impl<C: Coords> State<C> {
         self.grid.iter_within(&self.viewport)
     }
 
-    /// Scroll the viewport over the grid.
+    /// Scroll the viewport over the grid, by a delta in pixels of the grid.
+    /// Along an axis on which the grid is smaller than the viewport, the
+    /// grid is centered in the viewport instead.
     pub fn scroll(&mut self, scroll: scroll::Delta) {
         let grid  = self.grid.dimensions();
         let old_p = self.viewport.position;
@@ -83,25 +180,517 @@ impl<C: Coords> State<C> {
         let new_y = old_p.y + scroll.dy;
         let max_x = grid.width  - self.viewport.width;
         let max_y = grid.height - self.viewport.height;
-        self.viewport.position.x = f32::min(max_x, f32::max(0., new_x));
-        self.viewport.position.y = f32::min(max_y, f32::max(0., new_y));
+        self.viewport.position.x = clamp_scroll(new_x, max_x);
+        self.viewport.position.y = clamp_scroll(new_y, max_y);
+        if self.snap {
+            self.snap_to_grid();
+        }
+    }
+
+    /// Round the position of the viewport to integer screen pixels, i.e.
+    /// taking the [`State::scale`] into account, staying within the grid,
+    /// such that the edges of the hexagons are not drawn at fractional
+    /// pixels, which avoids "shimmering" edges while scrolling. For an
+    /// integral [`State::position`], the [`State::grid_position`] is thus
+    /// integral as well. Together with the hexagons of
+    /// [`Schema::hexagon_snapped`] at a scale of `1`, the corners of all
+    /// hexagons are drawn exactly on pixels, e.g. for pixel-art.
+    pub fn snap_to_grid(&mut self) {
+        let grid  = self.grid.dimensions();
+        let scale = self.scale;
+        let max_x = ((grid.width  - self.viewport.width)  * scale).floor();
+        let max_y = ((grid.height - self.viewport.height) * scale).floor();
+        let p = &mut self.viewport.position;
+        p.x = clamp_scroll((p.x * scale).round(), max_x).round() / scale;
+        p.y = clamp_scroll((p.y * scale).round(), max_y).round() / scale;
+    }
+
+    /// Whether the viewport is kept at integer pixels while scrolling,
+    /// see [`State::snap_to_grid`].
+    pub fn snapping(&self) -> bool {
+        self.snap
     }
 
-    /// Schedule a resize of the view for the next update.
+    /// Enable or disable keeping the viewport at integer pixels while
+    /// scrolling, see [`State::snap_to_grid`].
+    pub fn set_snapping(&mut self, snap: bool) {
+        self.snap = snap;
+        if snap {
+            self.snap_to_grid();
+        }
+    }
+
+    /// Scroll the viewport such that the given target, in pixel coordinates
+    /// of the grid, stays within the given dead-zone, whose position is
+    /// relative to the viewport. As long as the target is within the
+    /// dead-zone, the viewport does not move, otherwise the viewport is
+    /// scrolled just far enough to bring the target to the edge of the
+    /// dead-zone, e.g. to follow a moving unit.
+    pub fn follow(&mut self, target: Point2<f32>, deadzone: Bounds) {
+        let p = target - self.viewport.position.coords;
+        let delta = |v: f32, min: f32, len: f32| {
+            if v < min {
+                v - min
+            } else if v > min + len {
+                v - (min + len)
+            } else {
+                0.
+            }
+        };
+        let dx = delta(p.x, deadzone.position.x, deadzone.width);
+        let dy = delta(p.y, deadzone.position.y, deadzone.height);
+        if dx != 0. || dy != 0. {
+            self.scroll(scroll::Delta { dx, dy });
+        }
+    }
+
+    /// Resize the view to the given size on the screen, keeping its
+    /// position. See [`State::resize_window`] for laying out the view
+    /// within a window according to its [`Insets`].
     pub fn resize(&mut self, width: u32, height: u32) {
-        self.viewport.width  = width as f32;
-        self.viewport.height = height as f32;
+        self.viewport.width  = width as f32 / self.scale;
+        self.viewport.height = height as f32 / self.scale;
         // Adjust the viewport position according to the new size,
         // so it doesn't "jump" on the next scroll.
         self.scroll(scroll::Delta { dx: 0.0, dy: 0.0 });
     }
 
+    /// Move and resize the view for a window of the given size, such that
+    /// the view fills the window except for the configured [`Insets`].
+    pub fn resize_window(&mut self, width: f32, height: f32) {
+        let insets = self.insets;
+        self.position = Point2::new(insets.left, insets.top);
+        self.viewport.width  = f32::max(0., width - insets.left - insets.right) / self.scale;
+        self.viewport.height = f32::max(0., height - insets.top - insets.bottom) / self.scale;
+        self.scroll(scroll::Delta { dx: 0.0, dy: 0.0 });
+    }
+
     /// The current position of the grid (i.e. the top-left corner of the
     /// grid's bounding box) on the screen coordinate system.
     ///
-    /// Rendering the grid at this position "pulls" the viewport, which
-    /// moves across the grid, into the grid view.
+    /// Rendering the grid at this position, scaled by [`State::scale`],
+    /// "pulls" the viewport, which moves across the grid, into the grid view.
     pub fn grid_position(&self) -> Point2<f32> {
-        -self.viewport.position + self.position.coords
+        -self.viewport.position * self.scale + self.position.coords
+    }
+}
+
+/// Clamp the position of the viewport along an axis to `[0, max]`, or
+/// center the grid in the viewport if it is smaller, i.e. `max < 0`.
+fn clamp_scroll(v: f32, max: f32) -> f32 {
+    if max < 0. {
+        max / 2.
+    } else {
+        f32::min(max, f32::max(0., v))
+    }
+}
+
+/// A builder for the state of a scrollable grid view, combining
+/// the schema and shape of the grid with the bounds of the view.
+#[derive(Clone, Default)]
+pub struct Builder {
+    schema: Option<Schema>,
+    shape: Option<Shape<Vec<Cube>>>,
+    bounds: Option<Bounds>,
+}
+
+impl Builder {
+
+    pub fn new() -> Builder {
+        Self::default()
+    }
+
+    /// Set the schema of the hexagons of the grid.
+    pub fn schema(mut self, schema: Schema) -> Builder {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Set the shape of the grid.
+    pub fn shape<I>(mut self, shape: Shape<I>) -> Builder
+    where I: IntoIterator<Item=Cube> {
+        let total = shape.total;
+        let data = shape.into_iter().collect();
+        self.shape = Some(Shape { data, total });
+        self
+    }
+
+    /// Set the bounds of the view on the screen.
+    pub fn viewport(mut self, bounds: Bounds) -> Builder {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Build the state of the grid view, if the schema, shape
+    /// and viewport have all been set.
+    pub fn build<C: Coords>(self) -> Option<State<C>> {
+        let grid = Grid::new(self.schema?, self.shape?);
+        Some(State::new(grid, self.bounds?))
+    }
+}
+
+/// The layers of grid highlights, in the order in which they are drawn,
+/// i.e. later layers are drawn on top of earlier layers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Layer {
+    Range,
+    Path,
+    Selection,
+    Hover,
+}
+
+/// Highlighted tiles of a grid view, organised in layers, with each
+/// highlighted tile having a style `S` (e.g. a colour) that is
+/// interpreted by the renderer.
+#[derive(Clone, Debug)]
+pub struct Highlights<C: Coords, S> {
+    layers: BTreeMap<Layer, Entries<C, S>>,
+}
+
+/// The highlights of a single layer, in insertion order.
+#[derive(Clone, Debug)]
+struct Entries<C: Coords, S> {
+    order: Vec<(C, S)>,
+    index: HashMap<C, usize>,
+}
+
+impl<C: Coords, S> Default for Entries<C, S> {
+    fn default() -> Self {
+        Entries { order: Vec::new(), index: HashMap::new() }
+    }
+}
+
+impl<C: Coords, S> Entries<C, S> {
+    fn insert(&mut self, c: C, style: S) {
+        if let Some(i) = self.index.get(&c) {
+            self.order[*i].1 = style;
+        } else {
+            self.index.insert(c, self.order.len());
+            self.order.push((c, style));
+        }
+    }
+
+    fn remove(&mut self, c: &C) -> Option<S> {
+        let i = self.index.remove(c)?;
+        let (_, style) = self.order.remove(i);
+        for (c, _) in &self.order[i..] {
+            *self.index.get_mut(c).unwrap() -= 1;
+        }
+        Some(style)
+    }
+
+    fn get(&self, c: &C) -> Option<&S> {
+        self.index.get(c).map(|i| &self.order[*i].1)
+    }
+}
+
+impl<C: Coords, S> Default for Highlights<C, S> {
+    fn default() -> Self {
+        Highlights { layers: BTreeMap::new() }
+    }
+}
+
+impl<C: Coords, S> Highlights<C, S> {
+
+    pub fn new() -> Highlights<C, S> {
+        Self::default()
+    }
+
+    /// Highlight the given coordinates on a layer, replacing the style
+    /// of any previous highlight of the same coordinates on that layer,
+    /// which retains its position in the drawing order.
+    pub fn add(&mut self, layer: Layer, c: C, style: S) {
+        self.layers.entry(layer).or_default().insert(c, style);
+    }
+
+    /// Remove the highlight of the given coordinates from a layer.
+    pub fn remove(&mut self, layer: Layer, c: C) -> Option<S> {
+        self.layers.get_mut(&layer).and_then(|l| l.remove(&c))
+    }
+
+    /// Get the style of the highlighted coordinates on a layer.
+    pub fn get(&self, layer: Layer, c: C) -> Option<&S> {
+        self.layers.get(&layer).and_then(|l| l.get(&c))
+    }
+
+    /// Remove all highlights from a layer.
+    pub fn clear(&mut self, layer: Layer) {
+        self.layers.remove(&layer);
+    }
+
+    /// Remove all highlights from all layers.
+    pub fn clear_all(&mut self) {
+        self.layers.clear();
+    }
+
+    /// Iterate over all highlights in drawing order, i.e. layer by layer
+    /// and within a layer in the order in which they were added.
+    pub fn iter(&self) -> impl Iterator<Item=(Layer, C, &S)> + '_ {
+        self.layers.iter().flat_map(|(layer, l)|
+            l.order.iter().map(move |(c, s)| (*layer, *c, s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::{ Orientation, SideLength };
+    use crate::grid::shape;
+
+    #[test]
+    fn test_highlights_z_order() {
+        let mut hl = Highlights::new();
+        let a = Cube::origin();
+        let b = Cube::new_xz(1, 0);
+        hl.add(Layer::Hover, a, "hover");
+        hl.add(Layer::Selection, a, "selection");
+        hl.add(Layer::Path, b, "path");
+        hl.add(Layer::Range, a, "range");
+        hl.add(Layer::Range, b, "range");
+        let layers = hl.iter().map(|(l,_,_)| l).collect::<Vec<_>>();
+        assert_eq!(layers, vec![
+            Layer::Range, Layer::Range, Layer::Path, Layer::Selection, Layer::Hover
+        ]);
+        assert_eq!(hl.remove(Layer::Path, b), Some("path"));
+        assert_eq!(hl.get(Layer::Path, b), None);
+        hl.clear(Layer::Range);
+        let styles = hl.iter().map(|(_,_,s)| *s).collect::<Vec<_>>();
+        assert_eq!(styles, vec!["selection", "hover"]);
+        hl.clear_all();
+        assert_eq!(hl.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_highlights_insertion_order() {
+        let cs = Cube::origin().range(3).collect::<Vec<_>>();
+        let mut hl = Highlights::new();
+        for (i, c) in cs.iter().rev().enumerate() {
+            hl.add(Layer::Range, *c, i);
+        }
+        let order = hl.iter().map(|(_, c, _)| c).collect::<Vec<_>>();
+        assert_eq!(order, cs.iter().rev().cloned().collect::<Vec<_>>());
+        // Replacing a style keeps the position, removing one keeps the
+        // order of the others.
+        hl.add(Layer::Range, cs[5], 100);
+        assert_eq!(hl.remove(Layer::Range, cs[7]), Some(cs.len() - 8));
+        assert_eq!(hl.get(Layer::Range, cs[5]), Some(&100));
+        let order = hl.iter().map(|(_, c, _)| c).collect::<Vec<_>>();
+        let expected = cs.iter().rev().filter(|c| **c != cs[7]).cloned().collect::<Vec<_>>();
+        assert_eq!(order, expected);
+        assert_eq!(hl.iter().find(|(_, c, _)| *c == cs[5]).map(|(_, _, s)| *s), Some(100));
+    }
+
+    #[test]
+    fn test_builder() {
+        let schema = Schema::new(SideLength(20.), Orientation::PointyTop);
+        let bounds = Bounds { position: Point2::new(10., 20.), width: 300., height: 200. };
+        let built = Builder::new()
+            .schema(schema.clone())
+            .shape(shape::rectangle_zx_odd(10, 12))
+            .viewport(bounds)
+            .build::<Cube>()
+            .unwrap();
+        let grid = Grid::new(schema.clone(), shape::rectangle_zx_odd(10, 12));
+        let manual = State::<Cube>::new(grid, bounds);
+        assert_eq!(built.position(), manual.position());
+        assert_eq!(built.viewport().position, manual.viewport().position);
+        assert_eq!(built.width(), manual.width());
+        assert_eq!(built.height(), manual.height());
+        assert_eq!(built.grid().len(), manual.grid().len());
+        assert!(manual.grid().iter().all(|(c, h)| built.grid().get(*c) == Some(h)));
+        assert!(Builder::new().schema(schema).viewport(bounds).build::<Cube>().is_none());
+    }
+
+    #[test]
+    fn test_follow() {
+        let schema = Schema::new(SideLength(20.), Orientation::FlatTop);
+        let bounds = Bounds { position: Point2::new(0., 0.), width: 200., height: 100. };
+        let mut view = Builder::new()
+            .schema(schema)
+            .shape(shape::rectangle_xz_odd(40, 40))
+            .viewport(bounds)
+            .build::<Cube>()
+            .unwrap();
+        let deadzone = Bounds { position: Point2::new(50., 25.), width: 100., height: 50. };
+        // Moving within the dead-zone does not scroll.
+        view.follow(Point2::new(60., 30.), deadzone);
+        view.follow(Point2::new(140., 70.), deadzone);
+        assert_eq!(view.viewport().position, Point2::origin());
+        // Leaving the dead-zone scrolls just far enough.
+        view.follow(Point2::new(170., 90.), deadzone);
+        assert_eq!(view.viewport().position, Point2::new(20., 15.));
+        view.follow(Point2::new(160., 80.), deadzone);
+        assert_eq!(view.viewport().position, Point2::new(20., 15.));
+        view.follow(Point2::new(60., 30.), deadzone);
+        assert_eq!(view.viewport().position, Point2::new(10., 5.));
+        // Scrolling is limited by the grid.
+        view.follow(Point2::new(0., 0.), deadzone);
+        assert_eq!(view.viewport().position, Point2::origin());
+    }
+
+    #[test]
+    fn test_insets() {
+        let schema = Schema::new(SideLength(20.), Orientation::FlatTop);
+        let bounds = Bounds { position: Point2::new(0., 0.), width: 800., height: 600. };
+        let mut view = Builder::new()
+            .schema(schema)
+            .shape(shape::rectangle_xz_odd(40, 40))
+            .viewport(bounds)
+            .build::<Cube>()
+            .unwrap();
+        view.set_insets(200., 100., 100., 50.);
+        assert_eq!(view.position(), Point2::new(0., 0.));
+        view.resize_window(800., 600.);
+        assert_eq!(view.position(), Point2::new(200., 100.));
+        assert_eq!((view.width(), view.height()), (500., 450.));
+        // Resizing the window recomputes the view within the insets.
+        view.resize_window(1024.5, 768.);
+        assert_eq!(view.position(), Point2::new(200., 100.));
+        assert_eq!((view.width(), view.height()), (724.5, 618.));
+        assert!(view.from_pixel(Point2::new(150., 300.)).is_none());
+        assert!(view.from_pixel(Point2::new(250., 300.)).is_some());
+        // The view does not shrink below zero.
+        view.resize_window(250., 100.);
+        assert_eq!((view.width(), view.height()), (0., 0.));
+        // Resizing the view itself keeps its position, regardless of insets.
+        view.resize(300, 200);
+        assert_eq!(view.position(), Point2::new(200., 100.));
+        assert_eq!((view.width(), view.height()), (300., 200.));
+    }
+
+    #[test]
+    fn test_resize_keeps_position() {
+        let schema = Schema::new(SideLength(20.), Orientation::FlatTop);
+        let bounds = Bounds { position: Point2::new(50., 30.), width: 400., height: 300. };
+        let mut view = Builder::new()
+            .schema(schema)
+            .shape(shape::rectangle_xz_odd(40, 40))
+            .viewport(bounds)
+            .build::<Cube>()
+            .unwrap();
+        view.resize(640, 480);
+        assert_eq!(view.position(), bounds.position);
+        assert_eq!((view.width(), view.height()), (640., 480.));
+    }
+
+    #[test]
+    fn test_snap_to_grid() {
+        let schema = Schema::new(SideLength(17.3), Orientation::PointyTop);
+        let bounds = Bounds { position: Point2::new(0., 0.), width: 200.5, height: 100.5 };
+        let mut view = Builder::new()
+            .schema(schema)
+            .shape(shape::rectangle_zx_odd(20, 20))
+            .viewport(bounds)
+            .build::<Cube>()
+            .unwrap();
+        let is_integral = |p: Point2<f32>| p.x.fract() == 0. && p.y.fract() == 0.;
+        view.scroll(scroll::Delta { dx: 10.3, dy: 20.7 });
+        assert!(!is_integral(view.viewport().position));
+        view.snap_to_grid();
+        assert_eq!(view.viewport().position, Point2::new(10., 21.));
+        view.set_snapping(true);
+        for i in 0 .. 100 {
+            view.scroll(scroll::Delta { dx: 3.7, dy: if i < 50 { 4.4 } else { -4.4 } });
+            let p = view.viewport().position;
+            let dim = view.grid().dimensions();
+            assert!(is_integral(p));
+            assert!(p.x + view.width() <= dim.width && p.y + view.height() <= dim.height);
+        }
+        view.set_snapping(false);
+        view.scroll(scroll::Delta { dx: -0.5, dy: 0. });
+        assert!(!is_integral(view.viewport().position));
+        // When zoomed, the position of the grid on the screen is snapped.
+        let near_integral = |p: Point2<f32>|
+            (p.x - p.x.round()).abs() < 1e-3 && (p.y - p.y.round()).abs() < 1e-3;
+        view.set_snapping(true);
+        let anchor = Point2::new(100.3, 50.6);
+        let (c, _) = view.from_pixel(anchor).unwrap();
+        view.zoom(scroll::Scaling { scale: 1.37, anchor });
+        assert!(near_integral(view.grid_position()));
+        assert_eq!(view.from_pixel(anchor).map(|(c, _)| c), Some(c));
+        for i in 0 .. 100 {
+            view.scroll(scroll::Delta { dx: 3.7, dy: if i < 50 { 4.4 } else { -4.4 } });
+            let p = view.viewport().position;
+            let dim = view.grid().dimensions();
+            assert!(near_integral(view.grid_position()));
+            assert!(p.x + view.viewport().width <= dim.width + 1e-3);
+            assert!(p.y + view.viewport().height <= dim.height + 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_hover_selected() {
+        let schema = Schema::new(SideLength(20.), Orientation::FlatTop);
+        let bounds = Bounds { position: Point2::new(0., 0.), width: 200., height: 100. };
+        let mut view = Builder::new()
+            .schema(schema)
+            .shape(shape::hexagon(3))
+            .viewport(bounds)
+            .build::<Cube>()
+            .unwrap();
+        assert_eq!((view.hover(), view.selected()), (None, None));
+        let (a, b) = (Cube::origin(), Cube::new_xz(1, 0));
+        view.set_hover(Some(a));
+        view.set_selected(Some(b));
+        assert_eq!((view.hover(), view.selected()), (Some(a), Some(b)));
+        view.set_hover(Some(b));
+        assert_eq!((view.hover(), view.selected()), (Some(b), Some(b)));
+        view.set_hover(None);
+        view.set_selected(None);
+        assert_eq!((view.hover(), view.selected()), (None, None));
+    }
+
+    #[test]
+    fn test_fit() {
+        let schema = Schema::new(SideLength(20.), Orientation::FlatTop);
+        let bounds = Bounds { position: Point2::new(50., 30.), width: 400., height: 300. };
+        let mut view = Builder::new()
+            .schema(schema)
+            .shape(shape::rectangle_xz_odd(40, 20))
+            .viewport(bounds)
+            .build::<Cube>()
+            .unwrap();
+        view.scroll(scroll::Delta { dx: 100., dy: 50. });
+        view.fit();
+        let dim = view.grid().dimensions();
+        let (w, h) = (dim.width * view.scale(), dim.height * view.scale());
+        let p = view.grid_position();
+        let eps = 0.01;
+        // The whole grid is within the view, filling it along one axis.
+        assert!(view.scale() < 1.);
+        assert!(p.x >= bounds.position.x - eps && p.y >= bounds.position.y - eps);
+        assert!(p.x + w <= bounds.position.x + bounds.width + eps);
+        assert!(p.y + h <= bounds.position.y + bounds.height + eps);
+        assert!((w - bounds.width).abs() < eps || (h - bounds.height).abs() < eps);
+        // The grid is centered and the size of the view is unchanged.
+        assert!((p.x - bounds.position.x - (bounds.width - w) / 2.).abs() < eps);
+        assert!((p.y - bounds.position.y - (bounds.height - h) / 2.).abs() < eps);
+        assert!((view.width() - bounds.width).abs() < eps);
+        assert!((view.height() - bounds.height).abs() < eps);
+        assert_eq!(view.iter_viewport().count(), view.grid().len());
+        // Picking accounts for the scale.
+        let c = Cube::new_xz(10, -2);
+        let screen = p + view.grid().to_pixel(c).coords * view.scale();
+        assert_eq!(view.from_pixel(screen).map(|(c, _)| c), Some(c));
+    }
+
+    #[test]
+    fn test_zoom_anchor() {
+        let schema = Schema::new(SideLength(20.), Orientation::FlatTop);
+        let bounds = Bounds { position: Point2::new(10., 10.), width: 400., height: 300. };
+        let mut view = Builder::new()
+            .schema(schema)
+            .shape(shape::rectangle_xz_odd(40, 40))
+            .viewport(bounds)
+            .build::<Cube>()
+            .unwrap();
+        view.scroll(scroll::Delta { dx: 200., dy: 200. });
+        let anchor = Point2::new(110., 60.);
+        let (c, _) = view.from_pixel(anchor).unwrap();
+        view.zoom(scroll::Scaling { scale: 2., anchor });
+        assert_eq!(view.scale(), 2.);
+        assert_eq!(view.from_pixel(anchor).map(|(c, _)| c), Some(c));
+        assert!((view.width() - bounds.width).abs() < 0.01);
+        assert!((view.viewport().width - bounds.width / 2.).abs() < 0.01);
     }
 }
@@ -1,6 +1,8 @@
 
 use crate::geo::Bounds;
 
+use nalgebra::Point2;
+
 /// A border that is sensitive to points falling within
 /// the region determined by its bounds and width.
 pub struct Border {
@@ -16,11 +18,34 @@ pub struct Delta {
     pub dy: f32,
 }
 
+/// The edges of a [`Border`] that are active for a point,
+/// e.g. for showing directional scroll indicators.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub struct EdgeFlags {
+    pub left: bool,
+    pub right: bool,
+    pub top: bool,
+    pub bottom: bool,
+}
+
+impl EdgeFlags {
+    /// Whether any edge is active.
+    pub fn any(&self) -> bool {
+        self.left || self.right || self.top || self.bottom
+    }
+}
+
 impl Border {
     /// Evaluate the given point against this border, yielding a
     /// delta whose magnitude is determined by the proximity of the point
     /// to the bounds of the border.
     pub fn eval(&self, x: f32, y: f32) -> Delta {
+        self.eval_edges(x, y).0
+    }
+
+    /// Like [`Border::eval`], additionally yielding the edges of the
+    /// border within which the point falls.
+    pub fn eval_edges(&self, x: f32, y: f32) -> (Delta, EdgeFlags) {
         let left_min_x   = self.bounds.position.x;
         let left_max_x   = left_min_x + self.width;
         let right_min_x  = self.bounds.position.x + self.bounds.width - self.width;
@@ -30,11 +55,15 @@ impl Border {
         let bottom_min_y = self.bounds.position.y + self.bounds.height - self.width;
         let bottom_max_y = bottom_min_y + self.width;
 
+        let mut edges = EdgeFlags::default();
+
         let dx =
             if left_min_x <= x && x <= left_max_x {
+                edges.left = true;
                 (x - left_max_x - 1.) * self.scale
             }
             else if right_min_x <= x && x <= right_max_x {
+                edges.right = true;
                 (self.width + 1. - (left_min_x + self.bounds.width) + x) * self.scale
             }
             else {
@@ -43,16 +72,93 @@ impl Border {
 
         let dy =
             if top_min_y <= y && y <= top_max_y {
+                edges.top = true;
                 (y - top_max_y - 1.) * self.scale
             }
             else if bottom_min_y <= y && y <= bottom_max_y {
+                edges.bottom = true;
                 (self.width + 1. - (top_min_y + self.bounds.height) + y) * self.scale
             }
             else {
                 0.0
             };
 
-        Delta { dx, dy }
+        (Delta { dx, dy }, edges)
+    }
+}
+
+
+/// A zoom control that maps (mouse wheel) deltas to scale factors.
+pub struct Zoom {
+    /// The relative change of the scale per unit of a delta.
+    pub sensitivity: f32,
+    /// The minimum scale.
+    pub min: f32,
+    /// The maximum scale.
+    pub max: f32,
+}
+
+/// The result of evaluating a zoom delta.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Scaling {
+    /// The new scale.
+    pub scale: f32,
+    /// The point (in pixels) that is to remain fixed on the screen
+    /// when applying the new scale, e.g. the position of the cursor.
+    pub anchor: Point2<f32>,
+}
+
+impl Zoom {
+    /// Evaluate a zoom delta at the given point for the current scale,
+    /// yielding the new scale within the bounds of this zoom control.
+    /// Positive deltas zoom in, negative deltas zoom out, whereby
+    /// zooming in and out by the same delta yields the original scale.
+    pub fn eval(&self, scale: f32, delta: f32, x: f32, y: f32) -> Scaling {
+        let factor = (1. + self.sensitivity).powf(delta);
+        Scaling {
+            scale: f32::min(self.max, f32::max(self.min, scale * factor)),
+            anchor: Point2::new(x, y),
+        }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::*;
+
+    #[test]
+    fn prop_zoom_in_out() {
+        fn prop(n: u8, x: f32, y: f32) -> bool {
+            let zoom = Zoom { sensitivity: 0.1, min: 0.01, max: 100. };
+            let steps = n % 16;
+            let zoomed_in = (0 .. steps).fold(1., |s, _| zoom.eval(s, 1., x, y).scale);
+            let zoomed_out = (0 .. steps).fold(zoomed_in, |s, _| zoom.eval(s, -1., x, y).scale);
+            (zoomed_out - 1.).abs() < 0.001
+        }
+        quickcheck(prop as fn(_,_,_) -> _);
+    }
+
+    #[test]
+    fn test_zoom_clamp() {
+        let zoom = Zoom { sensitivity: 0.5, min: 0.5, max: 2. };
+        assert_eq!(zoom.eval(1., 10., 0., 0.).scale, 2.);
+        assert_eq!(zoom.eval(1., -10., 0., 0.).scale, 0.5);
+    }
+
+    #[test]
+    fn test_border_edges() {
+        let border = Border {
+            width: 10.,
+            scale: 1.,
+            bounds: Bounds { position: Point2::new(0., 0.), width: 100., height: 100. },
+        };
+        let (delta, edges) = border.eval_edges(2., 3.);
+        assert_eq!(edges, EdgeFlags { left: true, top: true, right: false, bottom: false });
+        assert!(delta.dx < 0. && delta.dy < 0.);
+        assert_eq!(delta, border.eval(2., 3.));
+        let (delta, edges) = border.eval_edges(50., 50.);
+        assert!(!edges.any());
+        assert_eq!(delta, Delta { dx: 0., dy: 0. });
+    }
+}
@@ -0,0 +1,3 @@
+//! Renderer-agnostic utilities, e.g. for the presentation of grids.
+
+pub mod color;
@@ -0,0 +1,32 @@
+//! Colors as RGBA components in the unit interval, i.e. independent
+//! of a particular renderer, e.g. for heatmaps or fading highlights.
+
+use crate::geo::Frac1;
+
+/// Linear interpolation between two RGBA colors, component-wise,
+/// yielding exactly `a` for `t = 0` and exactly `b` for `t = 1`.
+pub fn lerp_rgba(a: [f32; 4], b: [f32; 4], t: Frac1) -> [f32; 4] {
+    let t = f32::from(t);
+    let mut c = [0.; 4];
+    for i in 0 .. 4 {
+        c[i] = a[i] * (1. - t) + b[i] * t;
+    }
+    c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lerp_rgba() {
+        let a = [0.1, 0.2, 0.3, 1.0];
+        let b = [0.9, 0.6, 0.0, 0.5];
+        assert_eq!(lerp_rgba(a, b, Frac1::new(0., 1.)), a);
+        assert_eq!(lerp_rgba(a, b, Frac1::new(1., 1.)), b);
+        let mid = lerp_rgba(a, b, Frac1::new(1., 2.));
+        for i in 0 .. 4 {
+            assert!((mid[i] - (a[i] + b[i]) / 2.).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Renders a minimap of a randomly generated map.
+
+use ggez::{ ContextBuilder, Context, GameResult };
+use ggez::event::{ self, EventHandler };
+use ggez::graphics::{ self, Color, DrawParam, Mesh, BLACK };
+
+use hexacore::geo::{ Orientation, Schema, SideLength };
+use hexacore::grid::{ Cube, Grid, shape };
+use hexacore::grid::gen;
+
+use hexggez::mesh;
+
+struct State {
+    minimap: Mesh,
+}
+
+impl EventHandler for State {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, BLACK);
+        graphics::draw(ctx, &self.minimap, DrawParam::default())?;
+        graphics::present(ctx)
+    }
+}
+
+fn main() -> GameResult<()> {
+    let (ctx, game_loop) = &mut ContextBuilder::new("minimap", "hexggez").build()?;
+
+    let schema = Schema::new(SideLength(50.), Orientation::FlatTop);
+    let map: Grid<Cube, bool> = gen::random_map(schema, shape::hexagon(30), 42, 0.3);
+    let terrain = |c: &Cube| match map.payload(*c) {
+        Some(true) => Color::new(0.3, 0.6, 0.3, 1.0),
+        _          => Color::new(0.4, 0.4, 0.4, 1.0),
+    };
+    let minimap = mesh::minimap(ctx, &map, terrain, 0.1)?;
+
+    let state = &mut State { minimap };
+    event::run(ctx, game_loop, state)
+}
@@ -1,7 +1,8 @@
 
 pub mod menu;
 
-use hexacore::grid::Coords;
+use hexacore::geo;
+use hexacore::grid::{ Coords, Grid };
 use hexacore::ui::gridview;
 
 use ggez::*;
@@ -23,13 +24,79 @@ pub mod mesh {
             if let Some(hex) = view.grid().get(*t.borrow()) {
                 let hex_bounds = view.grid().schema().bounds(hex);
                 if view.viewport().intersects(&hex_bounds) {
-                    mesh.polygon(mode, hex.corners(), color)?;
+                    hexagon(mesh, mode, hex.corners(), color)?;
                 }
             }
         }
         Ok(())
     }
 
+    /// Add the corners of a hexagon of a grid to a mesh. Since these form
+    /// a convex polygon by construction, they are only validated (see
+    /// [`polygon`]) in debug builds, keeping the validation out of the
+    /// drawing of every frame in release builds.
+    fn hexagon(
+        mesh: &mut MeshBuilder,
+        mode: DrawMode,
+        corners: &[Point2<f32>],
+        color: Color,
+    ) -> GameResult<()> {
+        if cfg!(debug_assertions) {
+            return polygon(mesh, mode, corners, color)
+        }
+        mesh.polygon(mode, corners, color)?;
+        Ok(())
+    }
+
+    /// Add a polygon to a mesh, after validating that the corners form
+    /// a convex, non-degenerate polygon, which is the case for all
+    /// hexagons of a grid.
+    pub fn polygon(
+        mesh: &mut MeshBuilder,
+        mode: DrawMode,
+        corners: &[Point2<f32>],
+        color: Color,
+    ) -> GameResult<()> {
+        if !geo::is_convex_polygon(corners) {
+            let msg = format!("Invalid polygon: {:?}", corners);
+            return Err(GameError::RenderError(msg))
+        }
+        mesh.polygon(mode, corners, color)?;
+        Ok(())
+    }
+
+    /// Add the highlighted hexagons of a grid view to a mesh,
+    /// layer by layer.
+    pub fn highlights<C: Coords>(
+        view: &gridview::State<C>,
+        mesh: &mut MeshBuilder,
+        highlights: &gridview::Highlights<C, (DrawMode, Color)>,
+    ) -> GameResult<()> {
+        for (_, c, (mode, color)) in highlights.iter() {
+            hexagons(view, mesh, std::iter::once(c), *mode, *color)?;
+        }
+        Ok(())
+    }
+
+    /// Build a single mesh of all hexagons of a grid at the given scale,
+    /// filled with the colors given for the tiles, e.g. for rendering
+    /// a minimap without drawing every tile individually.
+    pub fn minimap<C: Coords, T>(
+        ctx: &mut Context,
+        grid: &Grid<C, T>,
+        colors: impl Fn(&C) -> Color,
+        scale: f32,
+    ) -> GameResult<Mesh> {
+        let mesh = &mut MeshBuilder::new();
+        for (c, hex) in grid.iter_painter() {
+            let corners = hex.corners().iter()
+                .map(|p| Point2::new(p.x * scale, p.y * scale))
+                .collect::<Vec<_>>();
+            hexagon(mesh, DrawMode::fill(), &corners, colors(c))?;
+        }
+        mesh.build(ctx)
+    }
+
 }
 
 pub mod image {
@@ -50,6 +117,26 @@ pub mod image {
         let img_dest = origin + img_pos.coords;
         img.draw(ctx, DrawParam::default().dest(img_dest))
     }
+
+    /// Draw an image centered on a hexagon, rotated by the given angle
+    /// (in radians) around its center, e.g. to show the heading of a unit.
+    pub fn draw_facing(
+        ctx: &mut Context,
+        img: &Image,
+        hex: &Hexagon,
+        origin: Point2<f32>,
+        angle: f32,
+    ) -> GameResult<()> {
+        img.draw(ctx, facing(hex, origin, angle))
+    }
+
+    /// The parameters for drawing an image with [`draw_facing`].
+    pub fn facing(hex: &Hexagon, origin: Point2<f32>, angle: f32) -> DrawParam {
+        DrawParam::default()
+            .dest(origin + hex.center().coords)
+            .offset(Point2::new(0.5, 0.5))
+            .rotation(angle)
+    }
 }
 
 pub mod text {
@@ -74,6 +161,18 @@ pub mod text {
 
 }
 
+pub mod color {
+    use super::*;
+    use hexacore::geo::Frac1;
+    use hexacore::util::color::lerp_rgba;
+
+    /// Linear interpolation between two colors, see
+    /// [`lerp_rgba`](hexacore::util::color::lerp_rgba).
+    pub fn lerp(a: Color, b: Color, t: Frac1) -> Color {
+        Color::from(lerp_rgba(a.into(), b.into(), t))
+    }
+}
+
 pub mod animation {
     use super::*;
     use std::borrow::Borrow;
@@ -139,7 +238,9 @@ pub mod animation {
         }
     }
 
-    // search::Path::to_pixel ?
+    /// Animate a movement along a path on a grid, with each step from one
+    /// hexagon to the next taking `secs` seconds at `ups` updates per second,
+    /// yielding the pixel position of every update.
     pub fn path<C, T>(ups: u16, secs: f32, grid: &Grid<C>, path: &[T]) -> PathIter
     where C: Coords,
           T: Borrow<C>
@@ -152,5 +253,168 @@ pub mod animation {
         PathIter::new(edges, steps_per_hex)
     }
 
+    /// The state of a [`Follower`] after advancing.
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub enum FollowState {
+        /// The follower moved to the given pixel position.
+        Moving(Point2<f32>),
+        /// The follower reached the end of the path.
+        Done,
+    }
+
+    /// Follows an animated path, see [`path`], keeping track of the
+    /// current pixel position, e.g. of a moving unit, to be rendered
+    /// at [`Follower::position`].
+    pub struct Follower {
+        path: PathIter,
+        position: Point2<f32>,
+    }
+
+    impl Follower {
+        /// Follow the given path, beginning at its start.
+        pub fn new(path: PathIter) -> Follower {
+            let position = path.edges.first().map_or(Point2::origin(), |e| e.0);
+            Follower { path, position }
+        }
+
+        /// The current pixel position.
+        pub fn position(&self) -> Point2<f32> {
+            self.position
+        }
+
+        /// Advance by a single step (i.e. update) along the path.
+        pub fn advance(&mut self) -> FollowState {
+            match self.path.next() {
+                Some(p) => {
+                    self.position = p;
+                    FollowState::Moving(p)
+                }
+                None => FollowState::Done
+            }
+        }
+    }
+
+    /// An animation revealing a range ring by ring, e.g. the reachable
+    /// coordinates of a selected unit, yielding the revealed coordinates
+    /// for every update. See [`range_reveal`].
+    pub struct RangeReveal<C> {
+        rings: Vec<Vec<C>>,
+        revealed: Vec<C>,
+        frames_per_ring: usize,
+        frame: usize,
+    }
+
+    impl<C: Coords> RangeReveal<C> {
+        /// Reveal the given rings, e.g. as obtained from
+        /// [`coords::reachable_rings`](hexacore::grid::coords::reachable_rings),
+        /// in order, whereby every ring is revealed for the given number of
+        /// frames (at least one) before the next ring is added.
+        pub fn new(rings: Vec<Vec<C>>, frames_per_ring: usize) -> RangeReveal<C> {
+            RangeReveal {
+                rings,
+                revealed: Vec::new(),
+                frames_per_ring: frames_per_ring.max(1),
+                frame: 0,
+            }
+        }
+    }
+
+    impl<C: Coords> Iterator for RangeReveal<C> {
+        type Item = Vec<C>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let ring = self.frame / self.frames_per_ring;
+            if ring >= self.rings.len() {
+                return None
+            }
+            if self.frame % self.frames_per_ring == 0 {
+                self.revealed.extend(self.rings[ring].iter().copied());
+            }
+            self.frame += 1;
+            Some(self.revealed.clone())
+        }
+    }
+
+    /// Animate the reveal of a range ring by ring, with each ring taking
+    /// `secs` seconds at `ups` updates per second.
+    pub fn range_reveal<C: Coords>(ups: u16, secs: f32, rings: Vec<Vec<C>>) -> RangeReveal<C> {
+        let frames_per_ring = (ups as f32 * secs).round() as usize;
+        RangeReveal::new(rings, frames_per_ring)
+    }
+
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hexacore::geo::{ Orientation, Schema, SideLength };
+    use hexacore::grid::{ coords, Cube, Grid, shape };
+
+    #[test]
+    fn test_degenerate_polygon() {
+        let mesh = &mut MeshBuilder::new();
+        let corners = [Point2::new(1., 1.); 6];
+        assert!(mesh::polygon(mesh, DrawMode::fill(), &corners, WHITE).is_err());
+        // A self-intersecting star, whose edges all turn in the same direction.
+        let star = [0, 2, 4, 1, 3].iter()
+            .map(|i| *i as f32 * 2. * std::f32::consts::PI / 5.)
+            .map(|a| Point2::new(a.cos(), a.sin()))
+            .collect::<Vec<_>>();
+        assert!(mesh::polygon(mesh, DrawMode::fill(), &star, WHITE).is_err());
+    }
+
+    #[test]
+    fn test_animation_updates_per_sec() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid = Grid::<Cube>::new(schema, shape::hexagon(3));
+        let path = [Cube::origin(), Cube::new_xz(1, 0), Cube::new_xz(2, 0)];
+        let slow = animation::path(30, 0.2, &grid, &path).collect::<Vec<_>>();
+        let fast = animation::path(60, 0.2, &grid, &path).collect::<Vec<_>>();
+        assert_eq!(slow.len() * 2, fast.len());
+        let step_slow = nalgebra::distance(&slow[0], &slow[1]);
+        let step_fast = nalgebra::distance(&fast[0], &fast[1]);
+        assert!((step_slow - 2. * step_fast).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_range_reveal() {
+        let rings = coords::reachable_rings(Cube::origin(), 3, |_| true);
+        let frames = animation::RangeReveal::new(rings.clone(), 1).collect::<Vec<_>>();
+        assert_eq!(frames.len(), rings.len());
+        for (i, frame) in frames.iter().enumerate() {
+            assert_eq!(frame.len(), coords::num_in_range(i as u16));
+            if i > 0 {
+                assert!(frames[i - 1].iter().all(|c| frame.contains(c)));
+                assert!(rings[i].iter().all(|c| frame.contains(c)));
+            }
+        }
+        let slow = animation::range_reveal(30, 0.1, rings.clone()).collect::<Vec<_>>();
+        assert_eq!(slow.len(), 3 * rings.len());
+        assert_eq!(slow[2], frames[0]);
+        assert_eq!(slow[3], frames[1]);
+    }
+
+    #[test]
+    fn test_image_facing() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let hex = schema.hexagon(Point2::new(20., 30.));
+        let param = image::facing(&hex, Point2::new(5., 5.), 1.5);
+        assert_eq!((param.dest.x, param.dest.y), (25., 35.));
+        assert_eq!((param.offset.x, param.offset.y), (0.5, 0.5));
+        assert_eq!(param.rotation, 1.5);
+    }
+
+    #[test]
+    fn test_follower() {
+        let schema = Schema::new(SideLength(10.), Orientation::FlatTop);
+        let grid = Grid::<Cube>::new(schema, shape::hexagon(3));
+        let path = [Cube::origin(), Cube::new_xz(1, 0)];
+        let mut follower = animation::Follower::new(animation::path(30, 0.1, &grid, &path));
+        assert_eq!(follower.position(), grid.to_pixel(path[0]));
+        for _ in 0 .. 3 {
+            assert!(matches!(follower.advance(), animation::FollowState::Moving(_)));
+        }
+        assert_eq!(follower.advance(), animation::FollowState::Done);
+        assert_eq!(follower.advance(), animation::FollowState::Done);
+    }
+}
@@ -39,7 +39,7 @@ struct State {
 
 impl EventHandler for State {
     fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
-        while timer::check_update_time(ctx, ui::UPDATES_PER_SEC as u32) {
+        while timer::check_update_time(ctx, self.ui.updates_per_sec() as u32) {
             // Process the command
             if let Some(input) = self.input.take() {
                 self.input = self.ui.apply(ctx, &mut self.world, input)?;
@@ -107,6 +107,18 @@ impl EventHandler for State {
             // End turn
             KeyCode::Return => Some(ui::Input::EndTurn()),
 
+            // Fast-forward / slow-motion
+            KeyCode::PageUp => {
+                let ups = self.ui.updates_per_sec();
+                self.ui.set_updates_per_sec(ups.saturating_mul(2));
+                None
+            }
+            KeyCode::PageDown => {
+                let ups = self.ui.updates_per_sec();
+                self.ui.set_updates_per_sec(ups / 2);
+                None
+            }
+
             // Unknown
             _ => None
         }
@@ -7,6 +7,7 @@ use hexacore::grid::coords;
 use hexacore::grid::Grid;
 use hexacore::grid::shape;
 use hexacore::ui::gridview;
+use hexacore::ui::gridview::Layer;
 use hexacore::ui::scroll;
 use hexacore::search;
 
@@ -28,8 +29,12 @@ pub const RED:  graphics::Color = graphics::Color { r: 1.,  g: 0.,  b: 0.,  a: 0
 pub const BLUE: graphics::Color = graphics::Color { r: 0.,  g: 0.,  b: 1.,  a: 1.  };
 pub const GREY: graphics::Color = graphics::Color { r: 0.5, g: 0.5, b: 0.5, a: 0.7 };
 
-pub const UPDATES_PER_SEC: u16 = 60;
-    const MOVE_HEX_SECS:   f32 = 0.15;
+/// The default number of updates per second of the game loop, at which
+/// movements take `MOVE_HEX_SECS` per hexagon.
+pub const DEFAULT_UPDATES_PER_SEC: u16 = 60;
+/// The maximum number of updates per second of the game loop.
+pub const MAX_UPDATES_PER_SEC: u16 = 8 * DEFAULT_UPDATES_PER_SEC;
+    const MOVE_HEX_SECS: f32 = 0.15;
 
 /// The input commands that drive the UI and game state.
 pub enum Input {
@@ -54,8 +59,9 @@ pub enum Input {
 pub struct State {
     view: gridview::State<world::Coords>,
     scroll_border: scroll::Border,
-    hover: Option<world::Coords>,
-    selected: Option<Selected>,
+    /// The movement range of the selected entity, if any. The selected
+    /// coordinates themselves are tracked by the view.
+    range: Option<MovementRange>,
     info: Option<Info>,
     turn: TurnTracker,
     panel: ControlPanel,
@@ -80,21 +86,21 @@ impl State {
         };
 
         // Setup the hexagonal grid
-        let schema = Schema::new(SideLength(50.), Orientation::FlatTop);
-        let grid = Grid::new(schema, shape::rectangle_xz_odd(30, 30));
-        let bounds = Bounds {
-            position: Point2::new(201., 101.),
-            width: width - 302.,
-            height: height - 302.,
-        };
-        let view = gridview::State::new(grid, bounds);
+        let mut view = gridview::Builder::new()
+            .schema(Schema::new(SideLength(50.), Orientation::FlatTop))
+            .shape(shape::rectangle_xz_odd(30, 30))
+            .viewport(Bounds { position: Point2::origin(), width, height })
+            .build()
+            .expect("grid view");
+        // Leave room for the HUD panels.
+        view.set_insets(201., 101., 101., 101.);
+        view.resize_window(width, height);
 
         State {
             view,
             scroll_border,
             turn: TurnTracker::new(turn),
-            selected: None,
-            hover: None,
+            range: None,
             info: None,
             panel: ControlPanel::main(ctx),
             settings: Settings::default(),
@@ -108,7 +114,7 @@ impl State {
     }
 
     pub fn hover(&self) -> Option<world::Coords> {
-        self.hover
+        self.view.hover()
     }
 
     pub fn get_scroll(&self, x: f32, y: f32) -> scroll::Delta {
@@ -119,6 +125,20 @@ impl State {
         &self.view
     }
 
+    /// The number of updates per second of the game loop.
+    pub fn updates_per_sec(&self) -> u16 {
+        self.settings.updates_per_sec
+    }
+
+    /// Change the number of updates per second of the game loop, within
+    /// `1 ..= MAX_UPDATES_PER_SEC`, e.g. for slow-motion or fast-forward.
+    /// Since movements are animated in a fixed number of updates, as given
+    /// by the `DEFAULT_UPDATES_PER_SEC`, all movements, including those in
+    /// progress, change speed accordingly.
+    pub fn set_updates_per_sec(&mut self, ups: u16) {
+        self.settings.updates_per_sec = ups.clamp(1, MAX_UPDATES_PER_SEC);
+    }
+
     /// Apply an input command, updating the UI and world state appropriately.
     /// Processing of one input optionally yields the next input to process,
     /// e.g. to repeat an operation.
@@ -130,7 +150,7 @@ impl State {
     ) -> GameResult<Option<Input>> {
         match input {
             Input::ResizeView { width, height } => {
-                self.view.resize(width as u32 - 302, height as u32 - 202);
+                self.view.resize_window(width, height);
                 let screen = graphics::Rect::new(0., 0., width, height);
                 graphics::set_screen_coordinates(ctx, screen)?;
                 graphics::present(ctx)?;
@@ -154,17 +174,15 @@ impl State {
             }
 
             Input::HoverHexagon { coords } => {
-                self.hover = coords;
+                self.view.set_hover(coords);
                 if let Some(c) = coords {
                     let entity = world.entity(c);
                     self.info = Some(Info::new(c, entity));
-                    if let Some(ref mut s) = self.selected {
-                        if let Some(ref mut r) = s.range {
-                            if entity.is_none() {
-                                r.path = r.range.path(c);
-                            } else {
-                                r.path = None;
-                            }
+                    if let Some(ref mut r) = self.range {
+                        if entity.is_none() {
+                            r.path = r.range.path(c);
+                        } else {
+                            r.path = None;
                         }
                     }
                 } else {
@@ -174,11 +192,10 @@ impl State {
             }
 
             Input::SelectHexagon { coords } => {
-                if self.selected.as_ref()
-                    .and_then(|s| s.range.as_ref())
+                if self.range.as_ref()
                     .and_then(|r| r.path.as_ref())
-                    .and_then(|p| p.back())
-                    .map_or(false, |n| Some(n.coords) == coords)
+                    .and_then(|p| p.last_coords())
+                    .map_or(false, |c| Some(c) == coords)
                 {
                     // Selected the target hexagon of the currently active
                     // movement path, thus execute the move.
@@ -187,7 +204,8 @@ impl State {
                     match coords {
                         Some(c) => self.select(ctx, c, world),
                         None => {
-                            self.selected = None;
+                            self.view.set_selected(None);
+                            self.range = None;
                             self.panel = ControlPanel::main(ctx)
                         }
                     };
@@ -204,17 +222,17 @@ impl State {
                         }
                     },
                     Button::NewAsteroid(size) => {
-                        if let Some(s) = &self.selected {
-                            if world.entity(s.coords).is_none() {
-                                world.new_asteroid(s.coords, size);
+                        if let Some(c) = self.view.selected() {
+                            if world.entity(c).is_none() {
+                                world.new_asteroid(c, size);
                             }
                         }
                     },
-                    Button::IncreaseCost => for s in &self.selected {
-                        world.increase_cost(s.coords);
+                    Button::IncreaseCost => for c in self.view.selected() {
+                        world.increase_cost(c);
                     },
-                    Button::DecreaseCost => for s in &self.selected {
-                        world.decrease_cost(s.coords);
+                    Button::DecreaseCost => for c in self.view.selected() {
+                        world.decrease_cost(c);
                     },
                     Button::ToggleGrid => {
                         self.settings.show_grid = !self.settings.show_grid;
@@ -249,12 +267,11 @@ impl State {
     ) -> bool {
         // Progress movement(s)
         if let Some(mv) = &mut self.movement {
-            if let Some(pos) = mv.pixel_path.next() {
-                mv.pixel_pos = pos;
-            }
-            else if let Some(mv) = self.movement.take() {
+            if mv.follower.advance() == animation::FollowState::Done {
                 // Movement is complete.
-                self.end_move(ctx, world, mv);
+                if let Some(mv) = self.movement.take() {
+                    self.end_move(ctx, world, mv);
+                }
             }
             true
         } else {
@@ -291,17 +308,19 @@ impl State {
         }
 
         // Selection
-        if let Some(ref s) = self.selected {
-            mesh.polygon(DrawMode::stroke(3.), s.hexagon.corners(), RED)?;
-            if let Some(ref r) = s.range {
-                let coords = r.range.iter().map(|(&c,_)| c).filter(|c| *c != s.coords);
-                mesh::hexagons(&self.view, mesh, coords, DrawMode::fill(), GREY)?;
-                r.path.as_ref().map_or(Ok(()), |p| {
-                    let path = p.iter().skip(1).map(|n| n.coords);
-                    mesh::hexagons(&self.view, mesh, path, DrawMode::stroke(3.), BLUE)
-                })?;
+        let mut highlights = gridview::Highlights::new();
+        if let Some(selected) = self.view.selected() {
+            highlights.add(Layer::Selection, selected, (DrawMode::stroke(3.), RED));
+            if let Some(ref r) = self.range {
+                for (c, _) in r.range.iter_sorted().filter(|(c,_)| *c != selected) {
+                    highlights.add(Layer::Range, c, (DrawMode::fill(), GREY));
+                }
+                for c in r.path.iter().flat_map(|p| p.coords().skip(1)) {
+                    highlights.add(Layer::Path, c, (DrawMode::stroke(3.), BLUE));
+                }
             }
         };
+        mesh::highlights(&self.view, mesh, &highlights)?;
 
         if let Ok(grid) = mesh.build(ctx) {
             graphics::draw(ctx, &grid, grid_dp)?;
@@ -320,7 +339,7 @@ impl State {
         if let Some(mv) = &self.movement {
             let img = mv.inner.entity.image(&mut self.assets.images);
             let vec = Vector2::new(img.width() as f32 / 2., img.height() as f32 / 2.);
-            let img_dest = grid_dest + mv.pixel_pos.coords - vec;
+            let img_dest = grid_dest + mv.follower.position().coords - vec;
             img.draw(ctx, DrawParam::default().dest(img_dest))?;
         }
 
@@ -361,14 +380,14 @@ impl State {
         world: &mut world::State,
         class: world::ShipClass
     ) -> Option<world::Coords> {
-        if let Some(s) = &self.selected {
-            if let Some(free) = coords::neighbours(s.coords)
+        if let Some(selected) = self.view.selected() {
+            if let Some(free) = coords::neighbours(selected)
                 .find_map(|n|
                     Some(n).filter(|o|
                         self.view.grid().get(*o).is_some() &&
                         world.entity(*o).is_none()))
             {
-                if world.new_ship(s.coords, free, class).is_some() {
+                if world.new_ship(selected, free, class).is_some() {
                     return Some(free)
                 }
             }
@@ -376,44 +395,46 @@ impl State {
         return None
     }
 
-    fn selected(&self,
+    fn movement_range(&self,
         coords: world::Coords,
-        hexagon: Hexagon,
         entity: Option<&world::Entity>,
         world: &world::State
-    ) -> Selected {
-        match entity {
-            None => Selected { coords, hexagon, range: None },
-            Some(entity) => {
-                let range = world.range(entity, coords, self.view.grid());
-                Selected {
-                    coords,
-                    hexagon,
-                    range: Some(MovementRange { range, path: None })
-                }
-            }
+    ) -> Option<MovementRange> {
+        entity.map(|entity| {
+            let range = world.range(entity, coords, self.view.grid());
+            MovementRange { range, path: None }
+        })
+    }
+
+    /// Select the given coordinates in the view, together with the
+    /// movement range of the entity at these coordinates, if any.
+    fn set_selected(&mut self, coords: world::Coords, world: &world::State) {
+        if self.view.grid().get(coords).is_some() {
+            self.view.set_selected(Some(coords));
+            self.range = self.movement_range(coords, world.entity(coords), world);
+        } else {
+            self.view.set_selected(None);
+            self.range = None;
         }
     }
 
     fn select(&mut self, ctx: &mut Context, coords: world::Coords, world: &world::State) {
-        let entity = world.entity(coords);
-        self.selected = self.view.grid().get(coords).map(|h|
-            self.selected(coords, h.clone(), entity, world));
-        self.panel = ControlPanel::hexagon(ctx, coords, entity);
+        self.set_selected(coords, world);
+        self.panel = ControlPanel::hexagon(ctx, coords, world.entity(coords));
     }
 
     fn begin_move(&mut self, world: &mut world::State) -> GameResult<()> {
         // Cut short / complete any previous movement.
         if let Some(prev) = self.movement.take() {
-            world.end_move(prev.inner);
+            world.end_move(prev.inner).ok();
         }
         // Take the currently selected movement path.
-        let path = self.selected.take()
-            .and_then(|s| s.range
-            .and_then(|r| r.path
-        )).unwrap_or(search::Path::empty());
+        self.view.set_selected(None);
+        let path = self.range.take()
+            .and_then(|r| r.path)
+            .unwrap_or(search::Path::empty());
         // Setup the new movement.
-        if let Some(world_move) = world.begin_move(path) {
+        if let Ok(world_move) = world.begin_move(path) {
             let ui_move = Movement::new(world_move, self.view.grid());
             if let Some(sound) = ui_move.inner.entity.sound(&mut self.assets.sounds) {
                 sound.play()?;
@@ -424,26 +445,30 @@ impl State {
     }
 
     fn end_move(&mut self, ctx: &mut Context, world: &mut world::State, mv: Movement) {
-        let goal = mv.inner.goal;
-        world.end_move(mv.inner);
-        let entity = world.entity(goal);
+        let (start, goal) = (mv.inner.start, mv.inner.goal);
+        // A rejected movement leaves the entity at the start,
+        // unless the start has been occupied meanwhile.
+        let at = match world.end_move(mv.inner) {
+            Ok(_) | Err(world::MoveError::StartOccupied) => goal,
+            Err(_) => start,
+        };
+        let entity = world.entity(at);
         // If nothing else has been selected meanwhile, select the
         // moved entity again to continue movement.
-        self.selected = self.selected.take().or_else(|| {
-            self.panel = ControlPanel::hexagon(ctx, goal, entity);
-            self.view.grid().get(goal).map(|h|
-                self.selected(goal, h.clone(), entity, world))
-        });
+        if self.view.selected().is_none() {
+            self.panel = ControlPanel::hexagon(ctx, at, entity);
+            self.set_selected(at, world);
+        }
     }
 
     fn end_turn(&mut self, ctx: &mut Context, world: &mut world::State) -> GameResult<()> {
         world.end_turn();
         // Refresh the control panel.
-        self.panel = match &self.selected {
+        self.panel = match self.view.selected() {
             None => ControlPanel::main(ctx),
-            Some(s) => {
-                let entity = s.range.as_ref().and_then(|_| world.entity(s.coords));
-                ControlPanel::hexagon(ctx, s.coords, entity)
+            Some(c) => {
+                let entity = self.range.as_ref().and_then(|_| world.entity(c));
+                ControlPanel::hexagon(ctx, c, entity)
             }
         };
         // Advance the turn tracker.
@@ -472,17 +497,18 @@ impl TurnTracker {
 
 pub struct Movement {
     inner: world::Movement,
-    pixel_path: animation::PathIter,
-    pixel_pos: Point2<f32>,
+    follower: animation::Follower,
 }
 
 impl Movement {
+    /// Animate a movement in a fixed number of updates, which take
+    /// `MOVE_HEX_SECS` per hexagon at the `DEFAULT_UPDATES_PER_SEC`.
     pub fn new(mv: world::Movement, grid: &Grid<world::Coords>) -> Movement {
-        let pixel_path = animation::path(UPDATES_PER_SEC, MOVE_HEX_SECS, grid, &mv.path);
+        let ups = DEFAULT_UPDATES_PER_SEC;
+        let pixel_path = animation::path(ups, MOVE_HEX_SECS, grid, &mv.path);
         Movement {
             inner: mv,
-            pixel_path,
-            pixel_pos: Point2::origin(),
+            follower: animation::Follower::new(pixel_path),
         }
     }
 }
@@ -496,6 +522,7 @@ pub struct Settings {
     show_grid: bool,
     show_coords: bool,
     show_cost: bool,
+    updates_per_sec: u16,
 }
 
 impl Default for Settings {
@@ -504,6 +531,7 @@ impl Default for Settings {
             show_grid: true,
             show_coords: true,
             show_cost: true,
+            updates_per_sec: DEFAULT_UPDATES_PER_SEC,
         }
     }
 }
@@ -609,9 +637,3 @@ impl Info {
     }
 }
 
-struct Selected {
-    coords: world::Coords,
-    hexagon: Hexagon,
-    range: Option<MovementRange>,
-}
-
@@ -43,44 +43,55 @@ impl State {
         search::astar::tree(at, None, &mut mvc)
     }
 
-    pub fn begin_move(&mut self, path: Path) -> Option<Movement> {
-        path.front()
-            .and_then(|start| path.back()
-                .and_then(|end|
-                    if start != end {
-                        Some((start.clone(), end.clone()))
-                    } else {
-                        None
-                    }))
-            .and_then(|(start, end)| {
-                if let Entry::Occupied(e) = self.entities.entry(start.coords) {
-                    if e.get().range() >= end.cost as u16 {
-                        Some(Movement {
-                            entity: e.remove(),
-                            start: start.coords,
-                            goal: end.coords,
-                            cost: end.cost,
-                            path: path.to_vec(),
-                        })
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            })
+    /// Begin moving the entity at the start of the given path along
+    /// the path, removing it from the world until the movement ends.
+    pub fn begin_move(&mut self, path: Path) -> Result<Movement, MoveError> {
+        let cost = path.total_cost();
+        let (start, end) = path.first_coords()
+            .and_then(|start| path.last_coords()
+                .filter(|end| start != *end)
+                .map(|end| (start, end)))
+            .ok_or(MoveError::NoPath)?;
+        if let Entry::Occupied(e) = self.entities.entry(start) {
+            let range = e.get().range();
+            if range as usize >= cost {
+                Ok(Movement {
+                    entity: e.remove(),
+                    start,
+                    goal: end,
+                    cost,
+                    path: path.to_vec(),
+                })
+            } else {
+                Err(MoveError::OutOfRange { cost, range })
+            }
+        } else {
+            Err(MoveError::NoEntity)
+        }
     }
 
-    pub fn end_move(&mut self, mv: Movement) -> &Entity {
+    /// End a movement, placing the entity at the goal and reducing its
+    /// range by the cost of the movement. If the cost exceeds the range
+    /// of the entity, the movement is rejected and the entity is placed
+    /// back at the start. If the start has been occupied in the meantime,
+    /// the entity is instead left at the goal, with its range unchanged.
+    pub fn end_move(&mut self, mv: Movement) -> Result<&Entity, MoveError> {
         let mut entity = mv.entity;
-        entity.reduce_range(mv.cost as u16);
-        &*match self.entities.entry(mv.goal) {
+        let range = entity.range();
+        let (at, result) = match entity.try_reduce_range(mv.cost) {
+            Some(()) => (mv.goal, Ok(())),
+            None if self.entities.contains_key(&mv.start) =>
+                (mv.goal, Err(MoveError::StartOccupied)),
+            None => (mv.start, Err(MoveError::OutOfRange { cost: mv.cost, range })),
+        };
+        let entity = &*match self.entities.entry(at) {
             Entry::Vacant(v) => v.insert(entity),
             Entry::Occupied(mut o) => {
                 o.insert(entity);
                 o.into_mut()
             }
-        }
+        };
+        result.map(|()| entity)
     }
 
     pub fn iter(&self) -> impl Iterator<Item=(&Coords, &Entity)> {
@@ -156,6 +167,20 @@ impl State {
     }
 }
 
+/// The reasons for rejecting a movement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveError {
+    /// The path does not lead anywhere.
+    NoPath,
+    /// There is no entity at the start of the path.
+    NoEntity,
+    /// The cost of the movement exceeds the range of the entity.
+    OutOfRange { cost: usize, range: u16 },
+    /// The movement was rejected, but the start has been occupied
+    /// during the movement, so the entity remains at the goal.
+    StartOccupied,
+}
+
 pub struct Movement {
     pub entity: Entity,
     pub start: Coords,
@@ -215,14 +240,22 @@ impl Entity {
         }
     }
 
+    /// Reduce the range of the entity, without falling below zero.
     pub fn reduce_range(&mut self, sub: u16) {
         match self {
-            Entity::Ship(ship)  => ship.range -= sub,
+            Entity::Ship(ship)  => ship.range = ship.range.saturating_sub(sub),
             Entity::Shipyard(_) => {}
             Entity::Asteroid(_) => {}
         }
     }
 
+    /// Reduce the range of the entity, if it is sufficient.
+    pub fn try_reduce_range(&mut self, sub: usize) -> Option<()> {
+        let range = (self.range() as usize).checked_sub(sub)?;
+        self.reduce_range(self.range() - range as u16);
+        Some(())
+    }
+
     pub fn sound<'a>(&'a self, sounds: &'a mut Sounds) -> Option<&'a mut audio::Source> {
         match self {
             Entity::Ship(ship) => Some(ship.class.sound(sounds)),
@@ -355,3 +388,75 @@ pub enum Asteroid {
     Large
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ship(world: &mut State, at: Coords, class: ShipClass) {
+        let yard_at = Coords::new(-5, -5);
+        world.new_shipyard(yard_at, Shipyard::new(100));
+        world.new_ship(yard_at, at, class);
+        world.entities.remove(&yard_at);
+    }
+
+    /// A path along the first row with the given costs per column.
+    fn path(costs: &[usize]) -> Path {
+        struct Costs<'a>(&'a [usize]);
+        impl<'a> search::Context<Coords> for Costs<'a> {
+            fn cost(&mut self, _from: Coords, to: Coords) -> Option<usize> {
+                self.0.get(to.col as usize).cloned()
+            }
+        }
+        let goal = Coords::new(costs.len() as i32 - 1, 0);
+        search::astar::path(Coords::new(0, 0), goal, &mut Costs(costs)).unwrap()
+    }
+
+    #[test]
+    fn test_begin_move_out_of_range() {
+        let mut world = State::new();
+        ship(&mut world, Coords::new(0, 0), ShipClass::Fighter);
+        // A fighter has a range of 2.
+        let p = path(&[0, 2, 2]);
+        assert_eq!(p.total_cost(), 4);
+        assert!(matches!(world.begin_move(p),
+            Err(MoveError::OutOfRange { cost: 4, range: 2 })));
+        assert!(world.entity(Coords::new(0, 0)).is_some());
+    }
+
+    #[test]
+    fn test_end_move_out_of_range() {
+        let mut world = State::new();
+        ship(&mut world, Coords::new(0, 0), ShipClass::Fighter);
+        let mut mv = world.begin_move(path(&[0, 1, 1])).ok().unwrap();
+        // The costs changed during the movement.
+        mv.cost = 3;
+        assert!(world.end_move(mv).is_err());
+        assert_eq!(world.entity(Coords::new(0, 0)).map(|e| e.range()), Some(2));
+        assert!(world.entity(Coords::new(2, 0)).is_none());
+    }
+
+    #[test]
+    fn test_end_move_start_occupied() {
+        let mut world = State::new();
+        ship(&mut world, Coords::new(0, 0), ShipClass::Fighter);
+        let mut mv = world.begin_move(path(&[0, 1, 1])).ok().unwrap();
+        mv.cost = 3;
+        // Another entity is placed at the start during the movement.
+        ship(&mut world, Coords::new(0, 0), ShipClass::Fighter);
+        assert_eq!(world.end_move(mv).err(), Some(MoveError::StartOccupied));
+        assert!(world.entity(Coords::new(0, 0)).is_some());
+        assert_eq!(world.entity(Coords::new(2, 0)).map(|e| e.range()), Some(2));
+        assert_eq!(world.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_reduce_range_saturating() {
+        let mut world = State::new();
+        ship(&mut world, Coords::new(0, 0), ShipClass::Fighter);
+        let mut entity = world.entities.remove(&Coords::new(0, 0)).unwrap();
+        assert!(entity.try_reduce_range(3).is_none());
+        assert_eq!(entity.range(), 2);
+        entity.reduce_range(3);
+        assert_eq!(entity.range(), 0);
+    }
+}